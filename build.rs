@@ -11,8 +11,10 @@ fn init_proto() -> Result<(), Box<dyn std::error::Error>> {
     );
 
     let files = &[
+        "proto/ratings_features_admin.proto",
         "proto/ratings_features_app.proto",
         "proto/ratings_features_chart.proto",
+        "proto/ratings_features_publisher.proto",
         "proto/ratings_features_user.proto",
         "proto/ratings_features_common.proto",
     ];
@@ -1,5 +1,5 @@
 use axum::{
-    extract::Path,
+    extract::{Path, Query},
     http::StatusCode,
     response::IntoResponse,
     routing::{get, post},
@@ -22,6 +22,7 @@ type State = Arc<RwLock<StateInner>>;
 pub struct StateInner {
     id_map: HashMap<String, String>, // id -> name
     categories: HashMap<String, Vec<String>>,
+    publishers: HashMap<String, String>, // id -> publisher id
 }
 
 #[tokio::main]
@@ -52,6 +53,7 @@ async fn main() {
 
 async fn register_snap(
     Path(snap_id): Path<String>,
+    Query(mut params): Query<HashMap<String, String>>,
     Extension(state): Extension<State>,
     categories: String,
 ) -> impl IntoResponse {
@@ -64,6 +66,9 @@ async fn register_snap(
     let snap_name = Uuid::new_v4().to_string();
 
     let mut guard = state.write().unwrap();
+    if let Some(publisher_id) = params.remove("publisher_id") {
+        guard.publishers.insert(snap_id.clone(), publisher_id);
+    }
     guard.id_map.insert(snap_id, snap_name.clone());
     guard.categories.insert(snap_name, categories);
 
@@ -80,7 +85,13 @@ async fn snap_assertions(
     match guard.id_map.get(&snap_id) {
         Some(name) => (
             StatusCode::OK,
-            json!({ "headers": { "snap-name": name } }).to_string(),
+            json!({
+                "headers": {
+                    "snap-name": name,
+                    "publisher-id": guard.publishers.get(&snap_id),
+                }
+            })
+            .to_string(),
         ),
 
         None => {
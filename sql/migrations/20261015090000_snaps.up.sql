@@ -0,0 +1,8 @@
+-- Metadata about snaps pulled from snapcraft.io so that we don't need to look it up on every request
+
+CREATE TABLE snaps (
+    snap_id CHAR(32) PRIMARY KEY,
+    name TEXT NOT NULL,
+    publisher TEXT,
+    last_refreshed TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
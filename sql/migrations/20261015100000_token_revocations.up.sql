@@ -0,0 +1,7 @@
+-- Tokens issued to a client before the time recorded here are no longer accepted. Rows are added
+-- when a user deletes their account and cleared if they authenticate again.
+
+CREATE TABLE token_revocations (
+    client_hash CHAR(64) PRIMARY KEY,
+    revoked_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
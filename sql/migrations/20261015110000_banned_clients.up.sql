@@ -0,0 +1,7 @@
+-- Clients that have been banned by an admin and may no longer authenticate
+
+CREATE TABLE banned_clients (
+    client_hash CHAR(64) PRIMARY KEY,
+    reason TEXT NOT NULL DEFAULT '',
+    banned_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
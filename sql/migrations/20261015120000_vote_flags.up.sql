@@ -0,0 +1,10 @@
+-- Votes flagged as suspicious, e.g. as part of a burst of votes from newly created users. Flagged
+-- votes can optionally be excluded when rating snaps.
+
+CREATE TABLE vote_flags (
+    vote_id INTEGER PRIMARY KEY REFERENCES votes(id) ON DELETE CASCADE,
+    reason TEXT NOT NULL,
+    flagged_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_votes_snap_id_created ON votes (snap_id, created);
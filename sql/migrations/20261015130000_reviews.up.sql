@@ -0,0 +1,15 @@
+-- Short written reviews of snaps, at most one per user per snap. The revision being reviewed is
+-- updated along with the review body each time it is resubmitted.
+
+CREATE TABLE reviews (
+    id SERIAL PRIMARY KEY,
+    user_id_fk INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    snap_id CHAR(32) NOT NULL,
+    snap_revision INT NOT NULL CHECK (snap_revision > 0),
+    body TEXT NOT NULL,
+    created TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX idx_reviews_unique_user_snap ON reviews (user_id_fk, snap_id);
+CREATE INDEX idx_reviews_snap_id ON reviews (snap_id, id);
@@ -0,0 +1,20 @@
+-- Reports of abusive reviews, forming a queue for moderators to work through. Reviews are hidden
+-- once a report against them has been actioned.
+
+ALTER TABLE reviews ADD COLUMN hidden_at TIMESTAMPTZ;
+
+CREATE TABLE content_reports (
+    id SERIAL PRIMARY KEY,
+    review_id INTEGER NOT NULL REFERENCES reviews(id) ON DELETE CASCADE,
+    reporter_id_fk INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    reason INTEGER NOT NULL,
+    details TEXT NOT NULL DEFAULT '',
+    state INTEGER NOT NULL DEFAULT 0,
+    created TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    resolved_at TIMESTAMPTZ,
+    CONSTRAINT reason CHECK (reason BETWEEN 1 AND 5),
+    CONSTRAINT state CHECK (state BETWEEN 0 AND 2)
+);
+
+CREATE UNIQUE INDEX idx_content_reports_unique_reporter ON content_reports (review_id, reporter_id_fk);
+CREATE INDEX idx_content_reports_state ON content_reports (state, id);
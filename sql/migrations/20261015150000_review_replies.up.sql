@@ -0,0 +1,9 @@
+-- Replies from snap publishers to reviews of their snaps, at most one per review.
+
+CREATE TABLE review_replies (
+    review_id INTEGER PRIMARY KEY REFERENCES reviews(id) ON DELETE CASCADE,
+    publisher_id TEXT NOT NULL,
+    body TEXT NOT NULL,
+    created TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
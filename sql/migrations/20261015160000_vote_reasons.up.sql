@@ -0,0 +1,4 @@
+-- The reasons given for negative votes, matching the VoteReason enum in the protobuf definitions.
+
+ALTER TABLE votes ADD COLUMN reasons INTEGER[] NOT NULL DEFAULT '{}';
+ALTER TABLE votes ADD CONSTRAINT reasons CHECK (reasons <@ ARRAY[1, 2, 3, 4, 5]);
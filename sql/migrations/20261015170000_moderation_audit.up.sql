@@ -0,0 +1,48 @@
+-- Moderation decisions outlive the content and votes they were made against. Reports keep a
+-- snapshot of the reported review so that they survive it being deleted, and votes are moderated
+-- per author and revision rather than per vote so that retracting and recasting the vote doesn't
+-- bring it back.
+
+ALTER TABLE content_reports
+    ADD COLUMN snap_id CHAR(32),
+    ADD COLUMN snap_revision INT,
+    ADD COLUMN review_body TEXT,
+    ADD COLUMN author_client_hash CHAR(64);
+
+UPDATE content_reports
+SET
+    snap_id = reviews.snap_id,
+    snap_revision = reviews.snap_revision,
+    review_body = reviews.body,
+    author_client_hash = users.client_hash
+FROM reviews
+INNER JOIN users ON users.id = reviews.user_id_fk
+WHERE reviews.id = content_reports.review_id;
+
+ALTER TABLE content_reports
+    ALTER COLUMN snap_id SET NOT NULL,
+    ALTER COLUMN snap_revision SET NOT NULL,
+    ALTER COLUMN review_body SET NOT NULL,
+    ALTER COLUMN author_client_hash SET NOT NULL,
+    ALTER COLUMN review_id DROP NOT NULL,
+    DROP CONSTRAINT content_reports_review_id_fkey,
+    ADD CONSTRAINT content_reports_review_id_fkey
+        FOREIGN KEY (review_id) REFERENCES reviews(id) ON DELETE SET NULL;
+
+CREATE INDEX idx_content_reports_author_snap ON content_reports (author_client_hash, snap_id);
+
+CREATE TABLE moderated_votes (
+    user_id_fk INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    snap_id CHAR(32) NOT NULL,
+    snap_revision INT NOT NULL,
+    moderated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id_fk, snap_id, snap_revision)
+);
+
+INSERT INTO moderated_votes (user_id_fk, snap_id, snap_revision, moderated_at)
+SELECT votes.user_id_fk, votes.snap_id, votes.snap_revision, vote_flags.flagged_at
+FROM vote_flags
+INNER JOIN votes ON votes.id = vote_flags.vote_id
+WHERE vote_flags.reason = 'moderated';
+
+DELETE FROM vote_flags WHERE reason = 'moderated';
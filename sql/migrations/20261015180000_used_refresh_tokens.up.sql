@@ -0,0 +1,12 @@
+-- Refresh tokens are single use: the ID of each one is recorded here when it is exchanged for new
+-- tokens and any later attempt to use it again is rejected. Rows can be deleted once the token
+-- has expired.
+
+CREATE TABLE used_refresh_tokens (
+    jti TEXT PRIMARY KEY,
+    client_hash CHAR(64) NOT NULL,
+    used_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX used_refresh_tokens_expires_at_idx ON used_refresh_tokens (expires_at);
@@ -0,0 +1,272 @@
+//! In-memory caching of vote summaries, charts and token revocations.
+//!
+//! Entries expire after a configurable TTL and each cache is bounded in size, evicting the oldest
+//! entries first once full. Writes to the votes table invalidate the cached data for the affected
+//! snap via [`invalidate_snap`] so that users see their own votes reflected immediately.
+//!
+//! Building with the `skip_cache` feature disables all caching.
+use crate::{db::VoteSummary, ratings::Chart, Config};
+use sqlx::types::time::OffsetDateTime;
+use std::{
+    borrow::Borrow,
+    collections::{HashMap, VecDeque},
+    hash::Hash,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex, OnceLock,
+    },
+    time::{Duration, Instant},
+};
+
+/// The default TTL for cached entries
+pub const DEFAULT_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+/// The default maximum number of cached vote summaries
+pub const DEFAULT_SUMMARY_MAX_ENTRIES: usize = 10_000;
+/// The default maximum number of cached charts
+pub const DEFAULT_CHART_MAX_ENTRIES: usize = 1_000;
+/// The default TTL for cached token revocations, which bounds how long it takes for a revocation
+/// made by another instance of the service to take effect
+pub const DEFAULT_REVOCATION_TTL: Duration = Duration::from_secs(30);
+/// The default maximum number of cached token revocations
+pub const DEFAULT_REVOCATION_MAX_ENTRIES: usize = 100_000;
+
+static SUMMARY_CACHE: OnceLock<Cache<String, VoteSummary>> = OnceLock::new();
+static CHART_CACHE: OnceLock<Cache<String, Chart>> = OnceLock::new();
+static REVOCATION_CACHE: OnceLock<Cache<String, Option<OffsetDateTime>>> = OnceLock::new();
+
+/// Initialise the global caches from the application config.
+///
+/// This has no effect if the caches have already been initialised, and if it is never called then
+/// the caches are created with their default settings on first use.
+pub fn init(config: &Config) {
+    SUMMARY_CACHE.get_or_init(|| {
+        Cache::new(
+            Duration::from_secs(config.cache_summary_ttl_secs),
+            config.cache_summary_max_entries,
+        )
+    });
+    CHART_CACHE.get_or_init(|| {
+        Cache::new(
+            Duration::from_secs(config.cache_chart_ttl_secs),
+            config.cache_chart_max_entries,
+        )
+    });
+    REVOCATION_CACHE.get_or_init(|| {
+        Cache::new(
+            Duration::from_secs(config.cache_revocation_ttl_secs),
+            config.cache_revocation_max_entries,
+        )
+    });
+}
+
+/// The cache of [`VoteSummary`]s for individual snaps, keyed by snap ID.
+pub fn vote_summaries() -> &'static Cache<String, VoteSummary> {
+    SUMMARY_CACHE.get_or_init(|| Cache::new(DEFAULT_TTL, DEFAULT_SUMMARY_MAX_ENTRIES))
+}
+
+/// The cache of computed [`Chart`]s, keyed by chart type and filters.
+pub fn charts() -> &'static Cache<String, Chart> {
+    CHART_CACHE.get_or_init(|| Cache::new(DEFAULT_TTL, DEFAULT_CHART_MAX_ENTRIES))
+}
+
+/// When tokens issued to each client were last revoked, keyed by client hash.
+///
+/// Clients without a revocation are cached as `None`, which is what spares most authenticated
+/// requests a trip to the DB.
+pub fn token_revocations() -> &'static Cache<String, Option<OffsetDateTime>> {
+    REVOCATION_CACHE
+        .get_or_init(|| Cache::new(DEFAULT_REVOCATION_TTL, DEFAULT_REVOCATION_MAX_ENTRIES))
+}
+
+/// Drop all cached data that includes votes for the given snap.
+///
+/// Charts that the snap does not currently appear in are left alone, so a snap newly qualifying
+/// for a chart will only show up once the cached chart expires.
+pub fn invalidate_snap(snap_id: &str) {
+    vote_summaries().remove(snap_id);
+    charts().retain(|_, chart| !chart.data.iter().any(|d| d.rating.snap_id == snap_id));
+}
+
+/// A snapshot of the usage counters for a [`Cache`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+}
+
+/// A size bounded cache with a fixed TTL for all entries.
+#[derive(Debug)]
+pub struct Cache<K, V> {
+    ttl: Duration,
+    max_entries: usize,
+    inner: Mutex<Entries<K, V>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+#[derive(Debug)]
+struct Entries<K, V> {
+    map: HashMap<K, (Instant, V)>,
+    // Insertion order for eviction. Keys that have since been removed or replaced are left in
+    // place and skipped over, so we check the insertion time against the map before evicting.
+    order: VecDeque<(K, Instant)>,
+}
+
+impl<K, V> Cache<K, V>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+{
+    pub fn new(ttl: Duration, max_entries: usize) -> Self {
+        Self {
+            ttl,
+            max_entries,
+            inner: Mutex::new(Entries {
+                map: HashMap::new(),
+                order: VecDeque::new(),
+            }),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Look up an unexpired entry, recording a hit or a miss.
+    pub fn get<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        if cfg!(feature = "skip_cache") {
+            return None;
+        }
+
+        let mut inner = self.inner.lock().unwrap();
+        let value = match inner.map.get(key) {
+            Some((inserted, v)) if inserted.elapsed() < self.ttl => Some(v.clone()),
+            Some(_) => {
+                inner.map.remove(key);
+                None
+            }
+            None => None,
+        };
+
+        let counter = if value.is_some() {
+            &self.hits
+        } else {
+            &self.misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+
+        value
+    }
+
+    /// Insert an entry, evicting the oldest entries if the cache is full.
+    pub fn insert(&self, key: K, value: V) {
+        if cfg!(feature = "skip_cache") || self.max_entries == 0 {
+            return;
+        }
+
+        let now = Instant::now();
+        let mut inner = self.inner.lock().unwrap();
+        inner.map.insert(key.clone(), (now, value));
+        inner.order.push_back((key, now));
+
+        while inner.map.len() > self.max_entries {
+            let Some((k, inserted)) = inner.order.pop_front() else {
+                break;
+            };
+            if inner.map.get(&k).is_some_and(|(t, _)| *t == inserted) {
+                inner.map.remove(&k);
+            }
+        }
+
+        if inner.order.len() > 2 * self.max_entries {
+            let Entries { map, order } = &mut *inner;
+            order.retain(|(k, inserted)| map.get(k).is_some_and(|(t, _)| t == inserted));
+        }
+    }
+
+    /// Remove the entry for the given key if there is one.
+    pub fn remove<Q>(&self, key: &Q)
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.inner.lock().unwrap().map.remove(key);
+    }
+
+    /// Remove all entries for which `f` returns false.
+    pub fn retain(&self, mut f: impl FnMut(&K, &V) -> bool) {
+        self.inner.lock().unwrap().map.retain(|k, (_, v)| f(k, v));
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: self.inner.lock().unwrap().map.len(),
+        }
+    }
+}
+
+#[cfg(all(test, not(feature = "skip_cache")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hits_and_misses_are_counted() {
+        let cache = Cache::new(DEFAULT_TTL, 10);
+
+        assert_eq!(cache.get("a"), None);
+        cache.insert("a".to_string(), 1);
+        assert_eq!(cache.get("a"), Some(1));
+        assert_eq!(cache.get("a"), Some(1));
+
+        assert_eq!(
+            cache.stats(),
+            CacheStats {
+                hits: 2,
+                misses: 1,
+                entries: 1
+            }
+        );
+    }
+
+    #[test]
+    fn expired_entries_are_not_returned() {
+        let cache = Cache::new(Duration::ZERO, 10);
+        cache.insert("a".to_string(), 1);
+
+        assert_eq!(cache.get("a"), None);
+        assert_eq!(cache.stats().entries, 0);
+    }
+
+    #[test]
+    fn oldest_entries_are_evicted_when_full() {
+        let cache = Cache::new(DEFAULT_TTL, 2);
+        cache.insert("a".to_string(), 1);
+        cache.insert("b".to_string(), 2);
+        cache.insert("a".to_string(), 3); // re-inserting refreshes "a"
+        cache.insert("c".to_string(), 4);
+
+        assert_eq!(cache.get("a"), Some(3));
+        assert_eq!(cache.get("b"), None);
+        assert_eq!(cache.get("c"), Some(4));
+    }
+
+    #[test]
+    fn entries_can_be_invalidated() {
+        let cache = Cache::new(DEFAULT_TTL, 10);
+        for (i, k) in ["a", "b", "c"].into_iter().enumerate() {
+            cache.insert(k.to_string(), i);
+        }
+
+        cache.remove("a");
+        cache.retain(|_, v| *v != 1);
+
+        assert_eq!(cache.get("a"), None);
+        assert_eq!(cache.get("b"), None);
+        assert_eq!(cache.get("c"), Some(2));
+    }
+}
@@ -1,7 +1,15 @@
 //! Utility functions and definitions for configuring the service.
+use crate::{
+    cache,
+    db::{BurstDetection, DEFAULT_MAX_REVIEW_LENGTH},
+    jwt,
+    middleware::RateLimits,
+    ratings::{snapcraft, BandThresholds, ScorerKind, DEFAULT_MIN_VOTES, DEFAULT_Z_SCORE},
+};
 use dotenvy::dotenv;
 use secrecy::SecretString;
 use serde::Deserialize;
+use std::time::Duration;
 
 /// Configuration for the general app center ratings backend service.
 #[derive(Deserialize, Debug, Clone)]
@@ -14,12 +22,163 @@ pub struct Config {
     pub postgres_uri: String,
     /// The JWT secret value
     pub jwt_secret: SecretString,
+    /// The issuer set on, and required of, JWTs
+    #[serde(default = "default_jwt_issuer")]
+    pub jwt_issuer: String,
+    /// How long, in seconds, access tokens are valid for
+    #[serde(default = "default_jwt_access_token_lifetime_secs")]
+    pub jwt_access_token_lifetime_secs: u64,
+    /// How long, in seconds, refresh tokens are valid for
+    #[serde(default = "default_jwt_refresh_token_lifetime_secs")]
+    pub jwt_refresh_token_lifetime_secs: u64,
+    /// How long, in seconds, admin tokens are valid for
+    #[serde(default = "default_jwt_admin_token_lifetime_secs")]
+    pub jwt_admin_token_lifetime_secs: u64,
+    /// How long, in seconds, publisher tokens are valid for
+    #[serde(default = "default_jwt_publisher_token_lifetime_secs")]
+    pub jwt_publisher_token_lifetime_secs: u64,
+    /// The ID of the key used to sign new JWTs, defaults to the key derived from `jwt_secret`
+    pub jwt_signing_kid: Option<String>,
+    /// Additional secrets that JWTs are verified but not signed with, as comma separated
+    /// `kid:secret` pairs
+    pub jwt_verify_secrets: Option<SecretString>,
+    /// A directory to load additional JWT keys from, see [`jwt::Keyring`]
+    pub jwt_key_dir: Option<String>,
     /// The base URI for snapcraft.io
     pub snapcraft_io_uri: String,
     /// The path to the tls keychain
     pub tls_keychain_path: Option<String>,
     /// The path to the tls private key
     pub tls_key_path: Option<String>,
+    /// The algorithm used to score snaps based on their votes
+    #[serde(default)]
+    pub rating_scorer: ScorerKind,
+    /// The Z-score used when scoring with the Wilson scorer
+    #[serde(default = "default_rating_z_score")]
+    pub rating_z_score: f64,
+    /// The prior positive ratio used when scoring with the Bayesian average scorer
+    #[serde(default = "default_rating_bayesian_prior_mean")]
+    pub rating_bayesian_prior_mean: f64,
+    /// The number of votes the prior is worth when scoring with the Bayesian average scorer
+    #[serde(default = "default_rating_bayesian_prior_weight")]
+    pub rating_bayesian_prior_weight: f64,
+    /// The number of votes below which a snap is rated as having insufficient votes
+    #[serde(default = "default_rating_min_votes")]
+    pub rating_min_votes: i64,
+    /// The score above which a snap is rated as very good
+    #[serde(default = "default_rating_band_good_upper")]
+    pub rating_band_good_upper: f64,
+    /// The score above which a snap is rated as good
+    #[serde(default = "default_rating_band_neutral_upper")]
+    pub rating_band_neutral_upper: f64,
+    /// The score above which a snap is rated as neutral
+    #[serde(default = "default_rating_band_poor_upper")]
+    pub rating_band_poor_upper: f64,
+    /// The score above which a snap is rated as poor rather than very poor
+    #[serde(default = "default_rating_band_very_poor_upper")]
+    pub rating_band_very_poor_upper: f64,
+    /// The half-life, in days, used to decay the weight of older votes. Decay is disabled if unset.
+    pub rating_decay_half_life_days: Option<f64>,
+    /// Whether votes flagged as suspicious are excluded when rating snaps
+    #[serde(default)]
+    pub rating_exclude_flagged_votes: bool,
+    /// The maximum number of snaps that ratings can be requested for in a single batch
+    #[serde(default = "default_rating_max_batch_size")]
+    pub rating_max_batch_size: usize,
+    /// How far back, in seconds, to look for a burst of votes on a snap from new users
+    #[serde(default = "default_fraud_burst_window_secs")]
+    pub fraud_burst_window_secs: u64,
+    /// How old, in seconds, a user can be when voting for their vote to count towards a burst
+    #[serde(default = "default_fraud_burst_new_user_max_age_secs")]
+    pub fraud_burst_new_user_max_age_secs: u64,
+    /// The number of votes from new users within the window at which they are flagged, or zero
+    /// to disable burst detection
+    #[serde(default = "default_fraud_burst_threshold")]
+    pub fraud_burst_threshold: i64,
+    /// The number of chart entries returned when a request does not specify a page size
+    #[serde(default = "default_chart_page_size")]
+    pub chart_default_page_size: u32,
+    /// The maximum number of chart entries that can be requested in a single page
+    #[serde(default = "default_chart_max_page_size")]
+    pub chart_max_page_size: u32,
+    /// The maximum length of a review, in characters
+    #[serde(default = "default_review_max_length")]
+    pub review_max_length: usize,
+    /// The number of reviews returned when a request does not specify a page size
+    #[serde(default = "default_review_page_size")]
+    pub review_default_page_size: u32,
+    /// The maximum number of reviews that can be requested in a single page
+    #[serde(default = "default_review_max_page_size")]
+    pub review_max_page_size: u32,
+    /// How long, in seconds, stored snap metadata is used before being refreshed from snapcraft.io
+    #[serde(default = "default_snap_metadata_ttl_secs")]
+    pub snap_metadata_ttl_secs: u64,
+    /// The maximum number of snaps to refresh metadata for at once when looking up snap names
+    #[serde(default = "default_snap_metadata_refresh_concurrency")]
+    pub snap_metadata_refresh_concurrency: usize,
+    /// How often, in seconds, categories are refreshed for recently voted on snaps
+    #[serde(default = "default_category_refresh_interval_secs")]
+    pub category_refresh_interval_secs: u64,
+    /// The maximum number of snaps to refresh categories for at once
+    #[serde(default = "default_category_refresh_concurrency")]
+    pub category_refresh_concurrency: usize,
+    /// How far back, in days, to look for votes when deciding which snaps to refresh
+    #[serde(default = "default_category_refresh_window_days")]
+    pub category_refresh_window_days: u32,
+    /// The timeout, in milliseconds, for a single request to snapcraft.io
+    #[serde(default = "default_snapcraft_timeout_ms")]
+    pub snapcraft_timeout_ms: u64,
+    /// The number of times a failed request to snapcraft.io is retried
+    #[serde(default = "default_snapcraft_max_retries")]
+    pub snapcraft_max_retries: u32,
+    /// The delay, in milliseconds, before the first retry of a failed request to snapcraft.io
+    #[serde(default = "default_snapcraft_retry_base_delay_ms")]
+    pub snapcraft_retry_base_delay_ms: u64,
+    /// The number of consecutive failed requests to snapcraft.io before we stop trying
+    #[serde(default = "default_snapcraft_breaker_threshold")]
+    pub snapcraft_breaker_threshold: u32,
+    /// How long, in seconds, to stop making requests to snapcraft.io for once the threshold is hit
+    #[serde(default = "default_snapcraft_breaker_cooldown_secs")]
+    pub snapcraft_breaker_cooldown_secs: u64,
+    /// How long, in seconds, the vote summary for a snap is cached for
+    #[serde(default = "default_cache_ttl_secs")]
+    pub cache_summary_ttl_secs: u64,
+    /// The maximum number of vote summaries to cache
+    #[serde(default = "default_cache_summary_max_entries")]
+    pub cache_summary_max_entries: usize,
+    /// How long, in seconds, charts are cached for
+    #[serde(default = "default_cache_ttl_secs")]
+    pub cache_chart_ttl_secs: u64,
+    /// The maximum number of charts to cache
+    #[serde(default = "default_cache_chart_max_entries")]
+    pub cache_chart_max_entries: usize,
+    /// How long, in seconds, token revocation lookups are cached for
+    #[serde(default = "default_cache_revocation_ttl_secs")]
+    pub cache_revocation_ttl_secs: u64,
+    /// The maximum number of token revocation lookups to cache
+    #[serde(default = "default_cache_revocation_max_entries")]
+    pub cache_revocation_max_entries: usize,
+    /// The number of `User/Authenticate` and `User/RefreshToken` requests allowed per minute from
+    /// a single IP address, or zero for no limit
+    #[serde(default = "default_rate_limit_authenticate_per_minute")]
+    pub rate_limit_authenticate_per_minute: u32,
+    /// The number of `User/Vote` and `User/RetractVote` requests allowed per minute from a single
+    /// client, or zero for no limit
+    #[serde(default = "default_rate_limit_vote_per_minute")]
+    pub rate_limit_vote_per_minute: u32,
+    /// The number of requests to any other RPC allowed per minute from a single client, or zero
+    /// for no limit
+    #[serde(default = "default_rate_limit_default_per_minute")]
+    pub rate_limit_default_per_minute: u32,
+    /// The port to serve prometheus metrics on
+    #[serde(default = "default_metrics_port")]
+    pub metrics_port: u16,
+    /// How often, in seconds, to check DB connectivity when reporting service health
+    #[serde(default = "default_health_check_interval_secs")]
+    pub health_check_interval_secs: u64,
+    /// How long, in seconds, to wait for in-flight requests to complete when shutting down
+    #[serde(default = "default_shutdown_drain_timeout_secs")]
+    pub shutdown_drain_timeout_secs: u64,
 }
 
 impl Config {
@@ -27,7 +186,46 @@ impl Config {
     pub fn load() -> envy::Result<Config> {
         dotenv().ok();
 
-        envy::prefixed("APP_").from_env::<Config>()
+        let config = envy::prefixed("APP_").from_env::<Config>()?;
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    /// Reject values that deserialize fine but that the service can't run with.
+    fn validate(&self) -> envy::Result<()> {
+        let must_be_positive = [
+            (
+                "APP_CATEGORY_REFRESH_INTERVAL_SECS",
+                self.category_refresh_interval_secs,
+            ),
+            (
+                "APP_CATEGORY_REFRESH_CONCURRENCY",
+                self.category_refresh_concurrency as u64,
+            ),
+            (
+                "APP_HEALTH_CHECK_INTERVAL_SECS",
+                self.health_check_interval_secs,
+            ),
+        ];
+
+        for (name, value) in must_be_positive {
+            if value == 0 {
+                return Err(envy::Error::Custom(format!("{name} must be at least 1")));
+            }
+        }
+
+        if let Some(days) = self.rating_decay_half_life_days {
+            let valid =
+                days > 0.0 && Duration::try_from_secs_f64(days * 24.0 * 60.0 * 60.0).is_ok();
+            if !valid {
+                return Err(envy::Error::Custom(
+                    "APP_RATING_DECAY_HALF_LIFE_DAYS must be a positive number of days".to_string(),
+                ));
+            }
+        }
+
+        Ok(())
     }
 
     /// Return a [`String`] representing the socket to run the service on
@@ -36,4 +234,185 @@ impl Config {
 
         format!("{host}:{port}")
     }
+
+    /// Return a [`String`] representing the socket to serve metrics on
+    pub fn metrics_socket(&self) -> String {
+        let Config {
+            metrics_port, host, ..
+        } = self;
+
+        format!("{host}:{metrics_port}")
+    }
+}
+
+fn default_jwt_issuer() -> String {
+    jwt::DEFAULT_ISSUER.to_string()
+}
+
+fn default_jwt_access_token_lifetime_secs() -> u64 {
+    jwt::DEFAULT_ACCESS_TOKEN_LIFETIME.whole_seconds() as u64
+}
+
+fn default_jwt_refresh_token_lifetime_secs() -> u64 {
+    jwt::DEFAULT_REFRESH_TOKEN_LIFETIME.whole_seconds() as u64
+}
+
+fn default_jwt_admin_token_lifetime_secs() -> u64 {
+    jwt::DEFAULT_ADMIN_TOKEN_LIFETIME.whole_seconds() as u64
+}
+
+fn default_jwt_publisher_token_lifetime_secs() -> u64 {
+    jwt::DEFAULT_PUBLISHER_TOKEN_LIFETIME.whole_seconds() as u64
+}
+
+fn default_rating_z_score() -> f64 {
+    DEFAULT_Z_SCORE
+}
+
+fn default_rating_bayesian_prior_mean() -> f64 {
+    0.5
+}
+
+fn default_rating_bayesian_prior_weight() -> f64 {
+    DEFAULT_MIN_VOTES as f64
+}
+
+fn default_rating_min_votes() -> i64 {
+    DEFAULT_MIN_VOTES
+}
+
+fn default_rating_band_good_upper() -> f64 {
+    BandThresholds::default().good_upper
+}
+
+fn default_rating_band_neutral_upper() -> f64 {
+    BandThresholds::default().neutral_upper
+}
+
+fn default_rating_band_poor_upper() -> f64 {
+    BandThresholds::default().poor_upper
+}
+
+fn default_rating_band_very_poor_upper() -> f64 {
+    BandThresholds::default().very_poor_upper
+}
+
+fn default_rating_max_batch_size() -> usize {
+    100
+}
+
+fn default_fraud_burst_window_secs() -> u64 {
+    BurstDetection::default().window.as_secs()
+}
+
+fn default_fraud_burst_new_user_max_age_secs() -> u64 {
+    BurstDetection::default().new_user_max_age.as_secs()
+}
+
+fn default_fraud_burst_threshold() -> i64 {
+    BurstDetection::default().threshold
+}
+
+fn default_chart_page_size() -> u32 {
+    20
+}
+
+fn default_chart_max_page_size() -> u32 {
+    100
+}
+
+fn default_review_max_length() -> usize {
+    DEFAULT_MAX_REVIEW_LENGTH
+}
+
+fn default_review_page_size() -> u32 {
+    20
+}
+
+fn default_review_max_page_size() -> u32 {
+    100
+}
+
+fn default_snap_metadata_ttl_secs() -> u64 {
+    7 * 24 * 60 * 60 // 1 week
+}
+
+fn default_snap_metadata_refresh_concurrency() -> usize {
+    8
+}
+
+fn default_category_refresh_interval_secs() -> u64 {
+    6 * 60 * 60 // 6 hours
+}
+
+fn default_category_refresh_concurrency() -> usize {
+    4
+}
+
+fn default_category_refresh_window_days() -> u32 {
+    30
+}
+
+fn default_snapcraft_timeout_ms() -> u64 {
+    snapcraft::DEFAULT_TIMEOUT.as_millis() as u64
+}
+
+fn default_snapcraft_max_retries() -> u32 {
+    snapcraft::DEFAULT_MAX_RETRIES
+}
+
+fn default_snapcraft_retry_base_delay_ms() -> u64 {
+    snapcraft::DEFAULT_RETRY_BASE_DELAY.as_millis() as u64
+}
+
+fn default_snapcraft_breaker_threshold() -> u32 {
+    snapcraft::DEFAULT_BREAKER_THRESHOLD
+}
+
+fn default_snapcraft_breaker_cooldown_secs() -> u64 {
+    snapcraft::DEFAULT_BREAKER_COOLDOWN.as_secs()
+}
+
+fn default_cache_ttl_secs() -> u64 {
+    cache::DEFAULT_TTL.as_secs()
+}
+
+fn default_cache_summary_max_entries() -> usize {
+    cache::DEFAULT_SUMMARY_MAX_ENTRIES
+}
+
+fn default_cache_chart_max_entries() -> usize {
+    cache::DEFAULT_CHART_MAX_ENTRIES
+}
+
+fn default_cache_revocation_ttl_secs() -> u64 {
+    cache::DEFAULT_REVOCATION_TTL.as_secs()
+}
+
+fn default_cache_revocation_max_entries() -> usize {
+    cache::DEFAULT_REVOCATION_MAX_ENTRIES
+}
+
+fn default_rate_limit_authenticate_per_minute() -> u32 {
+    RateLimits::default().authenticate_per_minute
+}
+
+fn default_rate_limit_vote_per_minute() -> u32 {
+    RateLimits::default().vote_per_minute
+}
+
+fn default_rate_limit_default_per_minute() -> u32 {
+    RateLimits::default().default_per_minute
+}
+
+fn default_metrics_port() -> u16 {
+    9090
+}
+
+fn default_health_check_interval_secs() -> u64 {
+    10
+}
+
+fn default_shutdown_drain_timeout_secs() -> u64 {
+    30
 }
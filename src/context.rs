@@ -1,15 +1,22 @@
 //! Application level context & state
 use crate::{
+    cache,
     config::Config,
-    jwt::{Error, JwtEncoder},
+    db::{BurstDetection, SummaryOptions},
+    jwt::{Error, JwtEncoder, JwtVerifier, Keyring},
+    ratings::{RatingCalculator, SnapcraftClient},
 };
-use std::{collections::HashMap, sync::Arc, time::Duration};
+use std::{collections::HashMap, sync::Arc};
 use tokio::sync::{Mutex, Notify};
 
 pub struct Context {
     pub config: Config,
     pub jwt_encoder: JwtEncoder,
-    pub http_client: reqwest::Client,
+    pub jwt_verifier: JwtVerifier,
+    pub snapcraft: SnapcraftClient,
+    pub rating_calculator: RatingCalculator,
+    pub summary_options: SummaryOptions,
+    pub burst_detection: BurstDetection,
 
     /// In progress category updates that we need to block on
     pub category_updates: Mutex<HashMap<String, Arc<Notify>>>,
@@ -17,14 +24,23 @@ pub struct Context {
 
 impl Context {
     pub fn new(config: Config) -> Result<Self, Error> {
-        let jwt_encoder = JwtEncoder::from_secret(&config.jwt_secret)?;
+        let keyring = Keyring::from_config(&config)?;
+        let jwt_encoder = JwtEncoder::from_config(&config, &keyring);
+        let jwt_verifier = JwtVerifier::from_config(&config, &keyring);
+        let rating_calculator = RatingCalculator::from_config(&config);
+        let summary_options = SummaryOptions::from_config(&config);
+        let burst_detection = BurstDetection::from_config(&config);
+        let snapcraft = SnapcraftClient::from_config(&config)?;
+        cache::init(&config);
 
         Ok(Self {
             config,
             jwt_encoder,
-            http_client: reqwest::Client::builder()
-                .pool_idle_timeout(Duration::from_secs(5))
-                .build()?,
+            jwt_verifier,
+            snapcraft,
+            rating_calculator,
+            summary_options,
+            burst_detection,
             category_updates: Default::default(),
         })
     }
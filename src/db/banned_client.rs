@@ -0,0 +1,93 @@
+use crate::{
+    cache,
+    db::{ClientHash, Result, User, Vote},
+};
+use sqlx::{types::time::OffsetDateTime, Connection, FromRow, PgConnection};
+
+/// A client that has been banned from rating snaps.
+#[derive(Debug, Clone, FromRow, PartialEq, Eq)]
+pub struct BannedClient {
+    /// The hash of the banned client
+    pub client_hash: ClientHash,
+    /// Why the client was banned
+    pub reason: String,
+    /// When the client was banned
+    pub banned_at: OffsetDateTime,
+}
+
+impl BannedClient {
+    /// Ban a client, revoking any tokens issued to them and optionally removing all of their
+    /// votes. Returns the number of votes removed.
+    pub async fn ban(
+        client_hash: &str,
+        reason: &str,
+        remove_votes: bool,
+        conn: &mut PgConnection,
+    ) -> Result<u64> {
+        let mut tx = conn.begin().await?;
+
+        sqlx::query(
+            r#"
+        INSERT INTO banned_clients (client_hash, reason, banned_at)
+        VALUES ($1, $2, NOW())
+        ON CONFLICT (client_hash)
+        DO UPDATE SET reason = EXCLUDED.reason
+        "#,
+        )
+        .bind(client_hash)
+        .bind(reason)
+        .execute(&mut *tx)
+        .await?;
+
+        User::revoke_tokens(client_hash, &mut tx).await?;
+
+        let snap_ids = if remove_votes {
+            Vote::delete_by_client_hash(client_hash, &mut tx).await?
+        } else {
+            Vec::new()
+        };
+
+        tx.commit().await?;
+        cache::token_revocations().remove(client_hash);
+
+        for snap_id in snap_ids.iter() {
+            cache::invalidate_snap(snap_id);
+        }
+
+        Ok(snap_ids.len() as u64)
+    }
+
+    /// Lift the ban on a client, returning whether they were banned.
+    pub async fn unban(client_hash: &str, conn: &mut PgConnection) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+        DELETE FROM banned_clients
+        WHERE client_hash = $1
+        "#,
+        )
+        .bind(client_hash)
+        .execute(conn)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Retrieves the ban for the given client, if they are banned.
+    pub async fn get_by_client_hash(
+        client_hash: &str,
+        conn: &mut PgConnection,
+    ) -> Result<Option<BannedClient>> {
+        let banned = sqlx::query_as(
+            r#"
+        SELECT client_hash, reason, banned_at
+        FROM banned_clients
+        WHERE client_hash = $1
+        "#,
+        )
+        .bind(client_hash)
+        .fetch_optional(conn)
+        .await?;
+
+        Ok(banned)
+    }
+}
@@ -1,5 +1,5 @@
 use crate::db::Result;
-use sqlx::{PgConnection, Postgres, QueryBuilder};
+use sqlx::{Connection, PgConnection, Postgres, QueryBuilder};
 
 #[derive(
     Debug,
@@ -64,3 +64,25 @@ pub async fn set_categories_for_snap(
 
     Ok(())
 }
+
+/// Atomically replace any existing categories for a snap with the provided ones.
+pub async fn replace_categories_for_snap(
+    snap_id: &str,
+    categories: Vec<Category>,
+    conn: &mut PgConnection,
+) -> Result<()> {
+    let mut tx = conn.begin().await?;
+
+    sqlx::query("DELETE FROM snap_categories WHERE snap_id = $1;")
+        .bind(snap_id)
+        .execute(&mut *tx)
+        .await?;
+
+    if !categories.is_empty() {
+        set_categories_for_snap(snap_id, categories, &mut tx).await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(())
+}
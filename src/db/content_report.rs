@@ -0,0 +1,268 @@
+//! Reports of abusive reviews, and the moderation queue built from them.
+//!
+//! Reports keep a snapshot of the review they were made against so that the moderation record
+//! survives the review being deleted.
+use crate::{
+    cache,
+    db::{ClientHash, Error, Result},
+};
+use sqlx::{types::time::OffsetDateTime, Connection, FromRow, PgConnection};
+
+/// Why a piece of content was reported.
+///
+/// The discriminants match the `ReportReason` enum in the common protobuf definition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, sqlx::Type, strum::FromRepr)]
+#[repr(i32)]
+pub enum ReportReason {
+    Spam = 1,
+    Abuse = 2,
+    OffTopic = 3,
+    Misleading = 4,
+    Other = 5,
+}
+
+/// Where a report is in the moderation queue.
+///
+/// The discriminants match the `ReportState` enum in the admin protobuf definition.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, sqlx::Type, strum::FromRepr)]
+#[repr(i32)]
+pub enum ReportState {
+    /// Awaiting a moderator
+    #[default]
+    Open = 0,
+    /// The content was found to be acceptable and left as it was
+    Dismissed = 1,
+    /// The content was hidden
+    Actioned = 2,
+}
+
+/// A report made by a user against a review.
+#[derive(Debug, Clone, FromRow, PartialEq, Eq)]
+pub struct ContentReport {
+    /// The ID of the report
+    pub id: i32,
+    /// The ID of the reported review, or `None` if it has since been deleted
+    pub review_id: Option<i32>,
+    /// The snap the reported review was written for
+    pub snap_id: String,
+    /// The text of the reported review as it currently stands, or as it was when last reported
+    /// if it has since been deleted
+    pub review_body: String,
+    /// The hash of the client that wrote the review
+    pub author_client_hash: ClientHash,
+    /// The hash of the client that made the report
+    pub reporter_client_hash: ClientHash,
+    /// Why the review was reported
+    pub reason: ReportReason,
+    /// Any further details given by the reporter
+    pub details: String,
+    /// Where the report is in the moderation queue
+    pub state: ReportState,
+    /// When the report was made
+    pub created: OffsetDateTime,
+    /// When the report was dismissed or actioned
+    pub resolved_at: Option<OffsetDateTime>,
+}
+
+impl ContentReport {
+    /// Report a review on behalf of the given [`ClientHash`]. Reporting the same review again
+    /// replaces the reason and details of the previous report.
+    ///
+    /// [`ClientHash`]: crate::db::ClientHash
+    pub async fn create(
+        review_id: i32,
+        reporter_client_hash: &str,
+        reason: ReportReason,
+        details: &str,
+        conn: &mut PgConnection,
+    ) -> Result<()> {
+        let result = sqlx::query(
+            r#"
+        INSERT INTO content_reports (
+            review_id, reporter_id_fk, reason, details,
+            snap_id, snap_revision, review_body, author_client_hash
+        )
+        SELECT reviews.id, reporters.id, $3, $4,
+            reviews.snap_id, reviews.snap_revision, reviews.body, authors.client_hash
+        FROM reviews
+        INNER JOIN users AS authors ON authors.id = reviews.user_id_fk
+        CROSS JOIN users AS reporters
+        WHERE reviews.id = $1
+        AND reviews.hidden_at IS NULL
+        AND reporters.client_hash = $2
+        ON CONFLICT (review_id, reporter_id_fk)
+        DO UPDATE SET
+            reason = EXCLUDED.reason,
+            details = EXCLUDED.details,
+            snap_revision = EXCLUDED.snap_revision,
+            review_body = EXCLUDED.review_body
+        "#,
+        )
+        .bind(review_id)
+        .bind(reporter_client_hash)
+        .bind(reason)
+        .bind(details)
+        .execute(conn)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(Error::ReviewNotFound);
+        }
+
+        Ok(())
+    }
+
+    /// Retrieves up to `limit` reports, optionally only those in the given state, from the
+    /// oldest to the most recent. If `after` is provided then only reports made after the report
+    /// with that ID are returned.
+    pub async fn list(
+        state: Option<ReportState>,
+        after: Option<i32>,
+        limit: i64,
+        conn: &mut PgConnection,
+    ) -> Result<Vec<ContentReport>> {
+        let reports = sqlx::query_as(
+            r#"
+            SELECT
+                content_reports.id,
+                content_reports.review_id,
+                content_reports.snap_id,
+                COALESCE(reviews.body, content_reports.review_body) AS review_body,
+                content_reports.author_client_hash,
+                reporters.client_hash AS reporter_client_hash,
+                content_reports.reason,
+                content_reports.details,
+                content_reports.state,
+                content_reports.created,
+                content_reports.resolved_at
+            FROM content_reports
+            LEFT JOIN reviews ON reviews.id = content_reports.review_id
+            INNER JOIN users AS reporters ON reporters.id = content_reports.reporter_id_fk
+            WHERE ($1::int IS NULL OR content_reports.state = $1)
+            AND ($2::int IS NULL OR content_reports.id > $2)
+            ORDER BY content_reports.id
+            LIMIT $3
+        "#,
+        )
+        .bind(state)
+        .bind(after)
+        .bind(limit)
+        .fetch_all(conn)
+        .await?;
+
+        Ok(reports)
+    }
+
+    /// Resolve an open report, returning the number of reports resolved or zero if there is no
+    /// open report with the given ID.
+    ///
+    /// Actioning a report hides the reported review, records that the author's vote on the
+    /// reviewed revision no longer counts towards the snap's rating, and resolves any other open
+    /// reports against the author's review of the snap. The author is then unable to review the
+    /// snap again (see [`ContentReport::has_actioned_review`]).
+    ///
+    /// [`ContentReport::has_actioned_review`]: crate::db::ContentReport::has_actioned_review
+    pub async fn resolve(id: i32, resolution: ReportState, conn: &mut PgConnection) -> Result<u64> {
+        let mut tx = conn.begin().await?;
+
+        let resolved: Option<(Option<i32>, String, i32, String)> = sqlx::query_as(
+            r#"
+        UPDATE content_reports
+        SET state = $2, resolved_at = NOW()
+        WHERE id = $1 AND state = $3
+        RETURNING review_id, snap_id, snap_revision, author_client_hash
+        "#,
+        )
+        .bind(id)
+        .bind(resolution)
+        .bind(ReportState::Open)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some((review_id, snap_id, snap_revision, author_client_hash)) = resolved else {
+            return Ok(0);
+        };
+
+        if resolution != ReportState::Actioned {
+            tx.commit().await?;
+            return Ok(1);
+        }
+
+        // Authors have at most one review per snap, so these are reports against the same review
+        let others = sqlx::query(
+            r#"
+        UPDATE content_reports
+        SET state = $3, resolved_at = NOW()
+        WHERE author_client_hash = $1 AND snap_id = $2 AND state = $4
+        "#,
+        )
+        .bind(&author_client_hash)
+        .bind(&snap_id)
+        .bind(ReportState::Actioned)
+        .bind(ReportState::Open)
+        .execute(&mut *tx)
+        .await?;
+
+        // Keep a record of the content that was actioned for as long as the review is around
+        sqlx::query(
+            r#"
+        UPDATE content_reports
+        SET review_body = reviews.body, snap_revision = reviews.snap_revision
+        FROM reviews
+        WHERE reviews.id = $1 AND content_reports.review_id = reviews.id
+        "#,
+        )
+        .bind(review_id)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("UPDATE reviews SET hidden_at = NOW() WHERE id = $1")
+            .bind(review_id)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query(
+            r#"
+        INSERT INTO moderated_votes (user_id_fk, snap_id, snap_revision)
+        SELECT id, $2, $3 FROM users WHERE client_hash = $1
+        ON CONFLICT DO NOTHING
+        "#,
+        )
+        .bind(&author_client_hash)
+        .bind(&snap_id)
+        .bind(snap_revision)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        cache::invalidate_snap(&snap_id);
+
+        Ok(1 + others.rows_affected())
+    }
+
+    /// Whether a moderator has taken action against a review of the snap written by the given
+    /// [`ClientHash`], in which case they may not review it again.
+    ///
+    /// [`ClientHash`]: crate::db::ClientHash
+    pub async fn has_actioned_review(
+        author_client_hash: &str,
+        snap_id: &str,
+        conn: &mut PgConnection,
+    ) -> Result<bool> {
+        let (actioned,): (bool,) = sqlx::query_as(
+            r#"
+        SELECT EXISTS (
+            SELECT 1 FROM content_reports
+            WHERE author_client_hash = $1 AND snap_id = $2 AND state = $3
+        )
+        "#,
+        )
+        .bind(author_client_hash)
+        .bind(snap_id)
+        .bind(ReportState::Actioned)
+        .fetch_one(conn)
+        .await?;
+
+        Ok(actioned)
+    }
+}
@@ -1,16 +1,32 @@
 use crate::Config;
 use sqlx::{postgres::PgPoolOptions, Connection, PgPool};
+use std::time::Duration;
 use thiserror::Error;
-use tokio::sync::OnceCell;
-use tracing::info;
+use tokio::{sync::OnceCell, time::timeout};
+use tracing::{info, warn};
 
+mod banned_client;
 mod categories;
+mod content_report;
+mod review;
+mod snap;
 mod user;
 mod vote;
-
-pub use categories::{set_categories_for_snap, snap_has_categories, Category};
+mod vote_flags;
+
+pub use banned_client::BannedClient;
+pub use categories::{
+    replace_categories_for_snap, set_categories_for_snap, snap_has_categories, Category,
+};
+pub use content_report::{ContentReport, ReportReason, ReportState};
+pub use review::{Review, DEFAULT_MAX_REVIEW_LENGTH};
+pub use snap::Snap;
 pub use user::User;
-pub use vote::{Timeframe, Vote, VoteSummary};
+pub use vote::{
+    RevisionVoteSummary, SummaryOptions, Timeframe, TrendingVoteSummary, Vote, VoteFilters,
+    VoteReason, VoteReasonCount, VoteStats, VoteSummary,
+};
+pub use vote_flags::{flag_vote_bursts, BurstDetection, BURST_FROM_NEW_USERS};
 
 #[macro_export]
 macro_rules! conn {
@@ -36,6 +52,21 @@ pub enum Error {
     #[error("failed to cast vote")]
     FailedToCastVote,
 
+    #[error("failed to retract vote")]
+    FailedToRetractVote,
+
+    #[error("failed to save review")]
+    FailedToSaveReview,
+
+    #[error("review not found")]
+    ReviewNotFound,
+
+    #[error("review was removed by a moderator")]
+    ReviewRemoved,
+
+    #[error("user not found")]
+    UserNotFound,
+
     #[error(transparent)]
     Migration(#[from] sqlx::migrate::MigrateError),
 
@@ -79,13 +110,33 @@ pub async fn check_db_conn() -> Result<()> {
     conn!().ping().await.map_err(Into::into)
 }
 
+/// Close all connections in the pool, waiting up to `wait` for any that are currently in use to
+/// be released.
+pub async fn close_pool(wait: Duration) {
+    if let Some(pool) = POOL.get() {
+        info!("Closing DB connection pool");
+        if timeout(wait, pool.close()).await.is_err() {
+            warn!(?wait, "timed out waiting for DB connections to be released");
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::conn;
-    use sqlx::types::time::OffsetDateTime;
+    use sqlx::{types::time::OffsetDateTime, PgConnection};
     use tracing_subscriber::EnvFilter;
 
+    /// Open a connection for a single test. Each test runs on its own runtime and pooled
+    /// connections don't survive the runtime they were opened on, so tests can't share the pool.
+    async fn test_conn() -> Result<PgConnection> {
+        let config = Config::load()?;
+        let mut conn = PgConnection::connect(&config.postgres_uri).await?;
+        sqlx::migrate!("sql/migrations").run(&mut conn).await?;
+
+        Ok(conn)
+    }
+
     #[cfg_attr(not(feature = "db_tests"), ignore)]
     #[tokio::test]
     async fn save_and_read_votes() -> Result<()> {
@@ -107,6 +158,7 @@ mod tests {
                 vote_up: true,
                 timestamp: OffsetDateTime::from_unix_timestamp(123).unwrap(),
                 snap_revision: 1,
+                reasons: Vec::new(),
             },
             vote::Vote {
                 client_hash: String::from(client_hash_2),
@@ -114,10 +166,11 @@ mod tests {
                 vote_up: false,
                 timestamp: OffsetDateTime::from_unix_timestamp(456).unwrap(),
                 snap_revision: 2,
+                reasons: vec![vote::VoteReason::Crashes, vote::VoteReason::Outdated],
             },
         ];
 
-        let conn = conn!();
+        let conn = &mut test_conn().await?;
 
         for client_hash in test_users.into_iter() {
             User::create_or_seen(client_hash, conn).await?;
@@ -150,6 +203,10 @@ mod tests {
         assert_eq!(second_vote.client_hash, client_hash_2);
         assert_eq!(second_vote.snap_revision, 2);
         assert!(!second_vote.vote_up);
+        assert_eq!(
+            second_vote.reasons,
+            [vote::VoteReason::Crashes, vote::VoteReason::Outdated]
+        );
 
         Ok(())
     }
@@ -157,7 +214,7 @@ mod tests {
     #[cfg_attr(not(feature = "db_tests"), ignore)]
     #[tokio::test]
     async fn update_categories() -> Result<()> {
-        let conn = conn!();
+        let conn = &mut test_conn().await?;
         let snap_id = "00000000000000000000000000000001";
         let cats = vec![categories::Category::ArtAndDesign];
 
@@ -173,4 +230,158 @@ mod tests {
 
         Ok(())
     }
+
+    #[cfg_attr(not(feature = "db_tests"), ignore)]
+    #[tokio::test]
+    async fn replace_categories() -> Result<()> {
+        let conn = &mut test_conn().await?;
+        let snap_id = "00000000000000000000000000000004";
+        let cats = vec![categories::Category::Games, categories::Category::Social];
+
+        categories::set_categories_for_snap(snap_id, cats, conn).await?;
+        categories::replace_categories_for_snap(snap_id, vec![categories::Category::Science], conn)
+            .await?;
+
+        let (n_rows,): (i64,) =
+            sqlx::query_as("SELECT COUNT(*) FROM snap_categories WHERE snap_id = $1;")
+                .bind(snap_id)
+                .fetch_one(&mut *conn)
+                .await?;
+        assert_eq!(n_rows, 1);
+
+        categories::replace_categories_for_snap(snap_id, vec![], conn).await?;
+        assert!(!categories::snap_has_categories(snap_id, conn).await?);
+
+        Ok(())
+    }
+
+    #[cfg_attr(not(feature = "db_tests"), ignore)]
+    #[tokio::test]
+    async fn save_and_read_snaps() -> Result<()> {
+        let conn = &mut test_conn().await?;
+        let snap_id = "00000000000000000000000000000003";
+
+        assert_eq!(Snap::get_by_snap_id(snap_id, conn).await?, None);
+
+        Snap::save(snap_id, "old-name", None, conn).await?;
+        Snap::save(snap_id, "new-name", Some("publisher"), conn).await?;
+
+        let snaps = Snap::get_by_snap_ids(&[snap_id.to_string()], conn).await?;
+        assert_eq!(snaps.len(), 1);
+        assert_eq!(snaps[0].snap_id, snap_id);
+        assert_eq!(snaps[0].name, "new-name");
+        assert_eq!(snaps[0].publisher.as_deref(), Some("publisher"));
+
+        Ok(())
+    }
+
+    #[cfg_attr(not(feature = "db_tests"), ignore)]
+    #[tokio::test]
+    async fn prune_token_revocations() -> Result<()> {
+        let conn = &mut test_conn().await?;
+        let client_hash = "0000000000000000000000000000000000000000000000000000000000000006";
+
+        User::revoke_tokens(client_hash, conn).await?;
+        let revoked_at = User::tokens_revoked_at(client_hash, conn).await?;
+        assert!(revoked_at.is_some());
+
+        // Revocations are kept until they are older than the cutoff
+        User::prune_token_revocations(revoked_at.unwrap(), conn).await?;
+        assert_eq!(
+            User::tokens_revoked_at(client_hash, conn).await?,
+            revoked_at
+        );
+
+        User::prune_token_revocations(OffsetDateTime::now_utc(), conn).await?;
+        assert_eq!(User::tokens_revoked_at(client_hash, conn).await?, None);
+
+        Ok(())
+    }
+
+    #[cfg_attr(not(feature = "db_tests"), ignore)]
+    #[tokio::test]
+    async fn old_votes_decay_to_nothing() -> Result<()> {
+        let conn = &mut test_conn().await?;
+        let client_hash = "0000000000000000000000000000000000000000000000000000000000000007";
+        let snap_id = "00000000000000000000000000000007";
+
+        User::create_or_seen(client_hash, conn).await?;
+        let vote = Vote {
+            client_hash: client_hash.to_string(),
+            snap_id: snap_id.to_string(),
+            snap_revision: 1,
+            vote_up: true,
+            timestamp: OffsetDateTime::now_utc(),
+            reasons: Vec::new(),
+        };
+        vote.save_to_db(conn).await?;
+        sqlx::query("UPDATE votes SET created = NOW() - INTERVAL '100 years' WHERE snap_id = $1")
+            .bind(snap_id)
+            .execute(&mut *conn)
+            .await?;
+
+        // Far more half-lives than a float8 can represent the weight of
+        let options = SummaryOptions {
+            decay_half_life: Some(Duration::from_secs(60)),
+            ..Default::default()
+        };
+        let per_revision = VoteSummary::get_by_snap_id_per_revision(snap_id, options, conn).await?;
+        let summaries = VoteSummary::get_by_snap_ids(&[snap_id.to_string()], options, conn).await?;
+        for summary in [&per_revision[0].summary, &summaries[0]] {
+            assert_eq!(summary.total_votes, 1);
+            assert!(summary.decayed_total_votes < 1e-300, "{summary:?}");
+        }
+
+        // Without decay every vote carries its full weight regardless of age
+        let per_revision =
+            VoteSummary::get_by_snap_id_per_revision(snap_id, SummaryOptions::default(), conn)
+                .await?;
+        assert_eq!(per_revision[0].summary.decayed_total_votes, 1.0);
+
+        Ok(())
+    }
+
+    #[cfg_attr(not(feature = "db_tests"), ignore)]
+    #[tokio::test]
+    async fn flagged_votes_can_be_excluded() -> Result<()> {
+        let conn = &mut test_conn().await?;
+        let snap_id = "00000000000000000000000000000005";
+        let detection = BurstDetection {
+            threshold: 3,
+            ..Default::default()
+        };
+
+        for i in 0..3 {
+            let client_hash = format!("{i:0>64}");
+            User::create_or_seen(&client_hash, conn).await?;
+            let vote = Vote {
+                client_hash,
+                snap_id: snap_id.to_string(),
+                snap_revision: 1,
+                vote_up: true,
+                timestamp: OffsetDateTime::now_utc(),
+                reasons: Vec::new(),
+            };
+            vote.save_to_db(conn).await?;
+
+            let flagged = flag_vote_bursts(snap_id, detection, conn).await?;
+            assert_eq!(flagged, if i == 2 { 3 } else { 0 });
+        }
+
+        let mut options = SummaryOptions::default();
+        let all = VoteSummary::get_by_snap_id_per_revision(snap_id, options, conn).await?;
+        options.exclude_flagged_votes = true;
+        let unflagged = VoteSummary::get_by_snap_id_per_revision(snap_id, options, conn).await?;
+
+        assert_eq!(all[0].summary.total_votes, 3);
+        assert!(unflagged.is_empty());
+        assert_eq!(
+            VoteStats::get_by_snap_id(snap_id, conn)
+                .await?
+                .flagged_votes,
+            3
+        );
+
+        Ok(())
+    }
 }
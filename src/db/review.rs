@@ -0,0 +1,206 @@
+use crate::db::{ClientHash, ContentReport, Error, Result};
+use sqlx::{types::time::OffsetDateTime, FromRow, PgConnection};
+use tracing::error;
+
+/// The maximum length of a review, in characters, unless otherwise configured
+pub const DEFAULT_MAX_REVIEW_LENGTH: usize = 500;
+
+/// The columns selected into a [`Review`], along with the joins they need
+const SELECT_REVIEWS: &str = r#"
+    SELECT
+        reviews.id,
+        users.client_hash,
+        reviews.snap_id,
+        reviews.snap_revision,
+        reviews.body,
+        reviews.created,
+        reviews.updated,
+        votes.vote_up,
+        review_replies.publisher_id AS reply_publisher_id,
+        review_replies.body AS reply_body,
+        review_replies.created AS reply_created,
+        review_replies.updated AS reply_updated
+    FROM reviews
+    INNER JOIN users ON users.id = reviews.user_id_fk
+    LEFT JOIN votes
+        ON votes.user_id_fk = reviews.user_id_fk
+        AND votes.snap_id = reviews.snap_id
+        AND votes.snap_revision = reviews.snap_revision
+    LEFT JOIN review_replies ON review_replies.review_id = reviews.id
+"#;
+
+/// A short written review of a snap, as submitted by a user
+#[derive(Debug, Clone, FromRow, PartialEq, Eq)]
+pub struct Review {
+    /// The ID of the review
+    pub id: i32,
+    /// The hash of the user client
+    pub client_hash: ClientHash,
+    /// The ID of the snap being reviewed
+    pub snap_id: String,
+    /// The revision of the snap being reviewed
+    #[sqlx(try_from = "i32")]
+    pub snap_revision: u32,
+    /// The text of the review
+    pub body: String,
+    /// When the review was first submitted
+    pub created: OffsetDateTime,
+    /// When the review was last changed
+    pub updated: OffsetDateTime,
+    /// The user's vote on the reviewed revision, if they have voted on it
+    pub vote_up: Option<bool>,
+    /// The publisher that replied to the review, if they have replied
+    pub reply_publisher_id: Option<String>,
+    /// The text of the publisher's reply
+    pub reply_body: Option<String>,
+    /// When the publisher first replied
+    pub reply_created: Option<OffsetDateTime>,
+    /// When the publisher's reply was last changed
+    pub reply_updated: Option<OffsetDateTime>,
+}
+
+impl Review {
+    /// Saves a review from the given [`ClientHash`], replacing any previous review they have
+    /// written for the snap. Users whose review of the snap was removed by a moderator may not
+    /// review it again.
+    ///
+    /// [`ClientHash`]: crate::db::ClientHash
+    pub async fn save(
+        client_hash: &str,
+        snap_id: &str,
+        snap_revision: u32,
+        body: &str,
+        conn: &mut PgConnection,
+    ) -> Result<()> {
+        if ContentReport::has_actioned_review(client_hash, snap_id, &mut *conn).await? {
+            return Err(Error::ReviewRemoved);
+        }
+
+        let result = sqlx::query(
+            r#"
+        INSERT INTO reviews (user_id_fk, snap_id, snap_revision, body)
+        SELECT id, $2, $3, $4 FROM users WHERE client_hash = $1
+        ON CONFLICT (user_id_fk, snap_id)
+        DO UPDATE SET
+            snap_revision = EXCLUDED.snap_revision,
+            body = EXCLUDED.body,
+            updated = NOW();
+        "#,
+        )
+        .bind(client_hash)
+        .bind(snap_id)
+        .bind(snap_revision as i32)
+        .bind(body)
+        .execute(conn)
+        .await
+        .map_err(|error| {
+            error!("{error:?}");
+            Error::FailedToSaveReview
+        })?;
+
+        if result.rows_affected() == 0 {
+            return Err(Error::UserNotFound);
+        }
+
+        Ok(())
+    }
+
+    /// Deletes the review from the given [`ClientHash`] for a snap, returning whether there was
+    /// a review to delete.
+    ///
+    /// [`ClientHash`]: crate::db::ClientHash
+    pub async fn delete(client_hash: &str, snap_id: &str, conn: &mut PgConnection) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+        DELETE FROM reviews
+        WHERE user_id_fk = (SELECT id FROM users WHERE client_hash = $1)
+        AND snap_id = $2;
+        "#,
+        )
+        .bind(client_hash)
+        .bind(snap_id)
+        .execute(conn)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Retrieves up to `limit` visible reviews for a snap, optionally only those of a specific revision,
+    /// from the most recently submitted to the oldest. If `after` is provided then only reviews
+    /// older than the review with that ID are returned.
+    pub async fn get_for_snap(
+        snap_id: &str,
+        snap_revision: Option<u32>,
+        after: Option<i32>,
+        limit: i64,
+        conn: &mut PgConnection,
+    ) -> Result<Vec<Review>> {
+        let reviews = sqlx::query_as(&format!(
+            r#"
+            {SELECT_REVIEWS}
+            WHERE reviews.snap_id = $1
+            AND reviews.hidden_at IS NULL
+            AND ($2::int IS NULL OR reviews.snap_revision = $2)
+            AND ($3::int IS NULL OR reviews.id < $3)
+            ORDER BY reviews.id DESC
+            LIMIT $4
+        "#
+        ))
+        .bind(snap_id)
+        .bind(snap_revision.map(|r| r as i32))
+        .bind(after)
+        .bind(limit)
+        .fetch_all(conn)
+        .await?;
+
+        Ok(reviews)
+    }
+
+    /// Retrieves the review with the given ID, unless it has been hidden by a moderator.
+    pub async fn get_by_id(id: i32, conn: &mut PgConnection) -> Result<Option<Review>> {
+        let review = sqlx::query_as(&format!(
+            r#"
+            {SELECT_REVIEWS}
+            WHERE reviews.id = $1
+            AND reviews.hidden_at IS NULL
+        "#
+        ))
+        .bind(id)
+        .fetch_optional(conn)
+        .await?;
+
+        Ok(review)
+    }
+
+    /// Saves a reply to a review from the publisher of the reviewed snap, replacing any previous
+    /// reply. Callers are responsible for checking that the publisher owns the snap.
+    pub async fn reply(
+        review_id: i32,
+        publisher_id: &str,
+        body: &str,
+        conn: &mut PgConnection,
+    ) -> Result<()> {
+        let result = sqlx::query(
+            r#"
+        INSERT INTO review_replies (review_id, publisher_id, body)
+        SELECT id, $2, $3 FROM reviews WHERE id = $1 AND hidden_at IS NULL
+        ON CONFLICT (review_id)
+        DO UPDATE SET
+            publisher_id = EXCLUDED.publisher_id,
+            body = EXCLUDED.body,
+            updated = NOW();
+        "#,
+        )
+        .bind(review_id)
+        .bind(publisher_id)
+        .bind(body)
+        .execute(conn)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(Error::ReviewNotFound);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,79 @@
+use crate::db::Result;
+use sqlx::{types::time::OffsetDateTime, FromRow, PgConnection};
+
+/// Metadata about a snap, as last fetched from snapcraft.io.
+#[derive(Debug, Clone, FromRow, PartialEq, Eq)]
+pub struct Snap {
+    /// The ID of the snap
+    pub snap_id: String,
+    /// The name of the snap
+    pub name: String,
+    /// The ID of the publisher of the snap, if known
+    pub publisher: Option<String>,
+    /// The time this metadata was last refreshed from snapcraft.io
+    pub last_refreshed: OffsetDateTime,
+}
+
+impl Snap {
+    /// Retrieves the stored metadata for the snap with the given ID, if we have any.
+    pub async fn get_by_snap_id(snap_id: &str, conn: &mut PgConnection) -> Result<Option<Snap>> {
+        let snap = sqlx::query_as(
+            r#"
+            SELECT snap_id, name, publisher, last_refreshed
+            FROM snaps
+            WHERE snap_id = $1
+        "#,
+        )
+        .bind(snap_id)
+        .fetch_optional(conn)
+        .await?;
+
+        Ok(snap)
+    }
+
+    /// Retrieves the stored metadata for each of the given snap IDs that we have metadata for.
+    pub async fn get_by_snap_ids(
+        snap_ids: &[String],
+        conn: &mut PgConnection,
+    ) -> Result<Vec<Snap>> {
+        let snaps = sqlx::query_as(
+            r#"
+            SELECT snap_id, name, publisher, last_refreshed
+            FROM snaps
+            WHERE snap_id = ANY($1)
+        "#,
+        )
+        .bind(snap_ids)
+        .fetch_all(conn)
+        .await?;
+
+        Ok(snaps)
+    }
+
+    /// Inserts or updates the metadata for a snap, marking it as having been refreshed now.
+    pub async fn save(
+        snap_id: &str,
+        name: &str,
+        publisher: Option<&str>,
+        conn: &mut PgConnection,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO snaps (snap_id, name, publisher, last_refreshed)
+            VALUES ($1, $2, $3, NOW())
+            ON CONFLICT (snap_id)
+            DO UPDATE SET
+                name = EXCLUDED.name,
+                publisher = EXCLUDED.publisher,
+                last_refreshed = EXCLUDED.last_refreshed
+        "#,
+        )
+        .bind(snap_id)
+        .bind(name)
+        .bind(publisher)
+        .execute(conn)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -1,5 +1,8 @@
-use crate::db::{ClientHash, Error, Result};
-use sqlx::{prelude::FromRow, types::time::OffsetDateTime, PgConnection};
+use crate::{
+    cache,
+    db::{ClientHash, Error, Result},
+};
+use sqlx::{prelude::FromRow, types::time::OffsetDateTime, Connection, PgConnection};
 use tracing::error;
 
 /// Information about a user who may be rating snaps.
@@ -38,7 +41,38 @@ impl User {
         Ok(user_with_id)
     }
 
+    /// Note that an existing user has been seen, returning false if there is no such user
+    pub async fn mark_seen(client_hash: &str, conn: &mut PgConnection) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+        UPDATE users
+        SET last_seen = NOW()
+        WHERE client_hash = $1
+        "#,
+        )
+        .bind(client_hash)
+        .execute(conn)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Delete a [`User`] along with all of their votes, revoking all tokens issued to them
     pub async fn delete_by_client_hash(client_hash: &str, conn: &mut PgConnection) -> Result<()> {
+        let mut tx = conn.begin().await?;
+
+        let snap_ids: Vec<(String,)> = sqlx::query_as(
+            r#"
+        SELECT DISTINCT votes.snap_id
+        FROM votes
+        INNER JOIN users ON users.id = votes.user_id_fk
+        WHERE users.client_hash = $1
+        "#,
+        )
+        .bind(client_hash)
+        .fetch_all(&mut *tx)
+        .await?;
+
         sqlx::query(
             r#"
         DELETE FROM users
@@ -46,13 +80,117 @@ impl User {
         "#,
         )
         .bind(client_hash)
-        .execute(conn)
+        .execute(&mut *tx)
         .await
         .map_err(|error| {
             error!("{error:?}");
             Error::FailedToDeleteUserRecord
         })?;
 
+        Self::revoke_tokens(client_hash, &mut tx).await?;
+        tx.commit().await?;
+        cache::token_revocations().remove(client_hash);
+
+        for (snap_id,) in snap_ids {
+            cache::invalidate_snap(&snap_id);
+        }
+
         Ok(())
     }
+
+    /// Revoke all tokens issued to the given client up until now.
+    ///
+    /// Callers should drop the client from [`cache::token_revocations`] once the revocation has
+    /// been committed.
+    pub async fn revoke_tokens(client_hash: &str, conn: &mut PgConnection) -> Result<()> {
+        sqlx::query(
+            r#"
+        INSERT INTO token_revocations (client_hash, revoked_at)
+        VALUES ($1, NOW())
+        ON CONFLICT (client_hash)
+        DO UPDATE SET revoked_at = EXCLUDED.revoked_at
+        "#,
+        )
+        .bind(client_hash)
+        .execute(conn)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Record that the refresh token with the given ID has been exchanged for new tokens,
+    /// returning false if it had already been used.
+    pub async fn use_refresh_token(
+        client_hash: &str,
+        jti: &str,
+        expires_at: OffsetDateTime,
+        conn: &mut PgConnection,
+    ) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+        INSERT INTO used_refresh_tokens (jti, client_hash, used_at, expires_at)
+        VALUES ($1, $2, NOW(), $3)
+        ON CONFLICT (jti) DO NOTHING
+        "#,
+        )
+        .bind(jti)
+        .bind(client_hash)
+        .bind(expires_at)
+        .execute(conn)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Delete the records of used refresh tokens that have since expired, returning the number
+    /// deleted.
+    pub async fn prune_used_refresh_tokens(conn: &mut PgConnection) -> Result<u64> {
+        let result = sqlx::query(
+            r#"
+        DELETE FROM used_refresh_tokens
+        WHERE expires_at < NOW()
+        "#,
+        )
+        .execute(conn)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// When tokens issued to the given client were last revoked, if they ever have been. Tokens
+    /// issued before then are no longer valid.
+    pub async fn tokens_revoked_at(
+        client_hash: &str,
+        conn: &mut PgConnection,
+    ) -> Result<Option<OffsetDateTime>> {
+        let revoked_at: Option<(OffsetDateTime,)> = sqlx::query_as(
+            r#"
+        SELECT revoked_at FROM token_revocations
+        WHERE client_hash = $1
+        "#,
+        )
+        .bind(client_hash)
+        .fetch_optional(conn)
+        .await?;
+
+        Ok(revoked_at.map(|(t,)| t))
+    }
+
+    /// Delete revocations made before `before`, returning the number deleted.
+    pub async fn prune_token_revocations(
+        before: OffsetDateTime,
+        conn: &mut PgConnection,
+    ) -> Result<u64> {
+        let result = sqlx::query(
+            r#"
+        DELETE FROM token_revocations
+        WHERE revoked_at < $1
+        "#,
+        )
+        .bind(before)
+        .execute(conn)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
 }
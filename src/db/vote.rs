@@ -1,6 +1,10 @@
-use crate::db::{categories::Category, ClientHash, Error, Result};
-use cached::proc_macro::cached;
-use sqlx::{types::time::OffsetDateTime, FromRow, PgConnection, QueryBuilder};
+use crate::{
+    cache,
+    db::{categories::Category, ClientHash, Error, Result},
+    Config,
+};
+use sqlx::{types::time::OffsetDateTime, FromRow, PgConnection, Postgres, QueryBuilder};
+use std::{collections::HashMap, time::Duration};
 use tracing::error;
 
 /// A Vote, as submitted by a user
@@ -18,6 +22,21 @@ pub struct Vote {
     /// The timestamp of the vote
     #[sqlx(rename = "created")]
     pub timestamp: OffsetDateTime,
+    /// Why the user voted the snap down, empty for positive votes
+    pub reasons: Vec<VoteReason>,
+}
+
+/// A reason given for a negative vote.
+///
+/// The discriminants match the `VoteReason` enum in the common protobuf definition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, sqlx::Type, strum::FromRepr)]
+#[repr(i32)]
+pub enum VoteReason {
+    Crashes = 1,
+    MissingFeatures = 2,
+    Performance = 3,
+    Outdated = 4,
+    Other = 5,
 }
 
 impl Vote {
@@ -37,6 +56,7 @@ impl Vote {
                     votes.snap_id,
                     votes.snap_revision,
                     votes.vote_up,
+                    votes.reasons,
                     users.client_hash
                 FROM
                     users
@@ -64,18 +84,20 @@ impl Vote {
 
     /// Saves a [`Vote`] to the database, if possible.
     pub async fn save_to_db(self, conn: &mut PgConnection) -> Result<u64> {
+        let snap_id = self.snap_id.clone();
         let result = sqlx::query(
             r#"
-        INSERT INTO votes (user_id_fk, snap_id, snap_revision, vote_up)
-        VALUES ((SELECT id FROM users WHERE client_hash = $1), $2, $3, $4)
+        INSERT INTO votes (user_id_fk, snap_id, snap_revision, vote_up, reasons)
+        SELECT id, $2, $3, $4, $5 FROM users WHERE client_hash = $1
         ON CONFLICT (user_id_fk, snap_id, snap_revision)
-        DO UPDATE SET vote_up = EXCLUDED.vote_up;
+        DO UPDATE SET vote_up = EXCLUDED.vote_up, reasons = EXCLUDED.reasons;
         "#,
         )
         .bind(self.client_hash)
         .bind(self.snap_id)
         .bind(self.snap_revision as i32)
         .bind(self.vote_up)
+        .bind(self.reasons)
         .execute(conn)
         .await
         .map_err(|error| {
@@ -83,16 +105,235 @@ impl Vote {
             Error::FailedToCastVote
         })?;
 
+        if result.rows_affected() == 0 {
+            return Err(Error::UserNotFound);
+        }
+
+        cache::invalidate_snap(&snap_id);
+
+        Ok(result.rows_affected())
+    }
+
+    /// Deletes the vote from the given [`ClientHash`] for a specific revision of a snap, returning
+    /// whether there was a vote to delete.
+    ///
+    /// [`ClientHash`]: crate::db::ClientHash
+    pub async fn delete(
+        client_hash: &str,
+        snap_id: &str,
+        snap_revision: u32,
+        conn: &mut PgConnection,
+    ) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+        DELETE FROM votes
+        WHERE user_id_fk = (SELECT id FROM users WHERE client_hash = $1)
+        AND snap_id = $2
+        AND snap_revision = $3;
+        "#,
+        )
+        .bind(client_hash)
+        .bind(snap_id)
+        .bind(snap_revision as i32)
+        .execute(conn)
+        .await
+        .map_err(|error| {
+            error!("{error:?}");
+            Error::FailedToRetractVote
+        })?;
+
+        let deleted = result.rows_affected() > 0;
+        if deleted {
+            cache::invalidate_snap(snap_id);
+        }
+
+        Ok(deleted)
+    }
+
+    /// Deletes all votes for a snap, or only those cast by the given [`ClientHash`], returning
+    /// the number of votes deleted.
+    ///
+    /// [`ClientHash`]: crate::db::ClientHash
+    pub async fn delete_for_snap(
+        snap_id: &str,
+        client_hash: Option<&str>,
+        conn: &mut PgConnection,
+    ) -> Result<u64> {
+        let result = sqlx::query(
+            r#"
+        DELETE FROM votes
+        WHERE snap_id = $1
+        AND ($2::text IS NULL OR user_id_fk = (SELECT id FROM users WHERE client_hash = $2));
+        "#,
+        )
+        .bind(snap_id)
+        .bind(client_hash)
+        .execute(conn)
+        .await
+        .map_err(|error| {
+            error!("{error:?}");
+            Error::FailedToRetractVote
+        })?;
+
+        cache::invalidate_snap(snap_id);
+
         Ok(result.rows_affected())
     }
+
+    /// Deletes all votes cast by the given [`ClientHash`], returning the IDs of the snaps that
+    /// had votes deleted.
+    ///
+    /// [`ClientHash`]: crate::db::ClientHash
+    pub async fn delete_by_client_hash(
+        client_hash: &str,
+        conn: &mut PgConnection,
+    ) -> Result<Vec<String>> {
+        let snap_ids: Vec<(String,)> = sqlx::query_as(
+            r#"
+        DELETE FROM votes
+        WHERE user_id_fk = (SELECT id FROM users WHERE client_hash = $1)
+        RETURNING snap_id;
+        "#,
+        )
+        .bind(client_hash)
+        .fetch_all(conn)
+        .await
+        .map_err(|error| {
+            error!("{error:?}");
+            Error::FailedToRetractVote
+        })?;
+
+        Ok(snap_ids.into_iter().map(|(snap_id,)| snap_id).collect())
+    }
+
+    /// Gets the IDs of all snaps that have been voted on since the given time.
+    pub async fn get_snap_ids_voted_since(
+        since: OffsetDateTime,
+        conn: &mut PgConnection,
+    ) -> Result<Vec<String>> {
+        let snap_ids: Vec<(String,)> =
+            sqlx::query_as("SELECT DISTINCT snap_id FROM votes WHERE created >= $1;")
+                .bind(since)
+                .fetch_all(conn)
+                .await?;
+
+        Ok(snap_ids.into_iter().map(|(snap_id,)| snap_id).collect())
+    }
+}
+
+/// The number of votes on a revision of a snap that gave a particular [`VoteReason`].
+#[derive(Debug, Clone, FromRow, PartialEq, Eq)]
+pub struct VoteReasonCount {
+    /// The revision of the snap the votes were cast on.
+    #[sqlx(try_from = "i32")]
+    pub snap_revision: u32,
+    /// The reason given.
+    pub reason: VoteReason,
+    /// The number of votes giving the reason.
+    pub votes: i64,
+}
+
+impl VoteReasonCount {
+    /// Counts the reasons given in votes on each revision of a snap, ordered from the most recent
+    /// revision to the oldest. The same votes are excluded as for a [`VoteSummary`].
+    pub async fn get_by_snap_id_per_revision(
+        snap_id: &str,
+        options: SummaryOptions,
+        conn: &mut PgConnection,
+    ) -> Result<Vec<VoteReasonCount>> {
+        let counts = sqlx::query_as(&format!(
+            r#"
+            SELECT
+                votes.snap_revision,
+                reason,
+                COUNT(*) AS votes
+            FROM
+                {}
+            CROSS JOIN UNNEST(votes.reasons) AS reason
+            WHERE
+                votes.snap_id = $1
+            GROUP BY votes.snap_revision, reason
+            ORDER BY votes.snap_revision DESC, reason
+        "#,
+            options.votes_table()
+        ))
+        .bind(snap_id)
+        .fetch_all(conn)
+        .await?;
+
+        Ok(counts)
+    }
+}
+
+/// Raw statistics about the votes cast for a snap, for use in moderation.
+#[derive(Debug, Clone, FromRow)]
+pub struct VoteStats {
+    /// The total votes this snap has received.
+    pub total_votes: i64,
+    /// The number of the votes which are positive.
+    pub positive_votes: i64,
+    /// The number of distinct users who have voted on the snap.
+    pub unique_voters: i64,
+    /// The number of votes that have been flagged as suspicious.
+    pub flagged_votes: i64,
+    /// When the oldest vote was cast, if there are any votes.
+    pub first_vote: Option<OffsetDateTime>,
+    /// When the most recent vote was cast, if there are any votes.
+    pub last_vote: Option<OffsetDateTime>,
+}
+
+impl VoteStats {
+    pub async fn get_by_snap_id(snap_id: &str, conn: &mut PgConnection) -> Result<VoteStats> {
+        let stats = sqlx::query_as(
+            r#"
+            SELECT
+                COUNT(*) AS total_votes,
+                COUNT(*) FILTER (WHERE vote_up) AS positive_votes,
+                COUNT(DISTINCT user_id_fk) AS unique_voters,
+                COUNT(vote_flags.vote_id) AS flagged_votes,
+                MIN(created) AS first_vote,
+                MAX(created) AS last_vote
+            FROM votes
+            LEFT JOIN vote_flags ON vote_flags.vote_id = votes.id
+            WHERE snap_id = $1
+        "#,
+        )
+        .bind(snap_id)
+        .fetch_one(conn)
+        .await?;
+
+        Ok(stats)
+    }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, strum::FromRepr)]
+/// The window of time over which votes are considered.
+///
+/// The discriminants match the `Timeframe` enum in the chart protobuf definition.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, strum::FromRepr)]
 #[repr(i32)]
 pub enum Timeframe {
-    Unspecified,
-    Week,
-    Month,
+    #[default]
+    Unspecified = 0,
+    Week = 1,
+    Month = 2,
+    Day = 3,
+    Quarter = 4,
+    Year = 5,
+    AllTime = 6,
+}
+
+impl Timeframe {
+    /// The length of this timeframe as a postgres interval, or `None` if it is unbounded.
+    pub fn interval(&self) -> Option<&'static str> {
+        match self {
+            Timeframe::Day => Some("1 day"),
+            Timeframe::Week => Some("1 week"),
+            Timeframe::Month => Some("1 month"),
+            Timeframe::Quarter => Some("3 months"),
+            Timeframe::Year => Some("1 year"),
+            Timeframe::Unspecified | Timeframe::AllTime => None,
+        }
+    }
 }
 
 /// A summary of votes for a given snap, this is then aggregated before transfer.
@@ -104,84 +345,518 @@ pub struct VoteSummary {
     pub total_votes: i64,
     /// The number of the votes which are positive.
     pub positive_votes: i64,
+    /// The total votes, each weighted by its age according to [`SummaryOptions`].
+    pub decayed_total_votes: f64,
+    /// The positive votes, each weighted by its age according to [`SummaryOptions`].
+    pub decayed_positive_votes: f64,
+}
+
+/// Options controlling how individual votes are aggregated into a [`VoteSummary`].
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct SummaryOptions {
+    /// When set, each vote contributes `0.5^(age / half_life)` to the decayed vote totals
+    /// rather than 1, so that older votes carry less weight than recent ones.
+    pub decay_half_life: Option<Duration>,
+    /// When set, votes that have been flagged as suspicious are not counted. Votes flagged by
+    /// moderators are never counted.
+    pub exclude_flagged_votes: bool,
+}
+
+impl SummaryOptions {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            decay_half_life: config
+                .rating_decay_half_life_days
+                .map(|days| Duration::from_secs_f64(days * 24.0 * 60.0 * 60.0)),
+            exclude_flagged_votes: config.rating_exclude_flagged_votes,
+        }
+    }
+
+    /// The source to select votes from, aliased as `votes` so that it can be used in place of
+    /// the `votes` table. Votes removed by a moderator are never included.
+    fn votes_table(&self) -> &'static str {
+        if self.exclude_flagged_votes {
+            r"(
+                SELECT * FROM votes
+                WHERE NOT EXISTS (
+                    SELECT 1 FROM moderated_votes
+                    WHERE moderated_votes.user_id_fk = votes.user_id_fk
+                    AND moderated_votes.snap_id = votes.snap_id
+                    AND moderated_votes.snap_revision = votes.snap_revision
+                )
+                AND NOT EXISTS (SELECT 1 FROM vote_flags WHERE vote_flags.vote_id = votes.id)
+            ) AS votes"
+        } else {
+            r"(
+                SELECT * FROM votes
+                WHERE NOT EXISTS (
+                    SELECT 1 FROM moderated_votes
+                    WHERE moderated_votes.user_id_fk = votes.user_id_fk
+                    AND moderated_votes.snap_id = votes.snap_id
+                    AND moderated_votes.snap_revision = votes.snap_revision
+                )
+            ) AS votes"
+        }
+    }
+
+    /// The half-life in seconds, bound as the parameter for the decayed vote totals.
+    ///
+    /// Vote ages are capped at 1000 half-lives when decaying as Postgres raises an underflow
+    /// error rather than returning 0 once the weight is too small to represent. When unset the
+    /// decayed totals are NULL and fall back to the plain vote counts.
+    fn half_life_secs(&self) -> Option<f64> {
+        self.decay_half_life.map(|d| d.as_secs_f64())
+    }
+}
+
+/// A [`VoteSummary`] restricted to the votes cast on a single revision of a snap.
+#[derive(Debug, Clone, FromRow)]
+pub struct RevisionVoteSummary {
+    /// The revision of the snap these votes were cast on.
+    #[sqlx(try_from = "i32")]
+    pub snap_revision: u32,
+    /// The summary of votes for this revision.
+    #[sqlx(flatten)]
+    pub summary: VoteSummary,
 }
 
 impl VoteSummary {
-    pub async fn get_by_snap_id(snap_id: &str, conn: &mut PgConnection) -> Result<VoteSummary> {
-        get_by_snap_id_cached(snap_id, conn).await
+    pub async fn get_by_snap_id(
+        snap_id: &str,
+        options: SummaryOptions,
+        conn: &mut PgConnection,
+    ) -> Result<VoteSummary> {
+        if let Some(summary) = cache::vote_summaries().get(snap_id) {
+            return Ok(summary);
+        }
+
+        let summary = fetch_by_snap_id(snap_id, options, conn).await?;
+        cache::vote_summaries().insert(snap_id.to_string(), summary.clone());
+
+        Ok(summary)
     }
 
-    /// Retrieves the vote summary over a given [Timeframe], optionally for a specific [Category]
-    pub async fn get_for_timeframe(
-        timeframe: Timeframe,
-        category: Option<Category>,
+    /// Retrieves the vote summary for each of the given snaps, in the same order. Snaps that have
+    /// received no votes are given an empty summary.
+    ///
+    /// Summaries are served from the cache where possible, with the rest fetched in a single
+    /// query.
+    pub async fn get_by_snap_ids(
+        snap_ids: &[String],
+        options: SummaryOptions,
         conn: &mut PgConnection,
     ) -> Result<Vec<VoteSummary>> {
-        let mut builder = QueryBuilder::new(
-            r"
+        let mut summaries: HashMap<String, VoteSummary> = HashMap::with_capacity(snap_ids.len());
+        let mut missing = Vec::new();
+        for snap_id in snap_ids {
+            match cache::vote_summaries().get(snap_id) {
+                Some(summary) => {
+                    summaries.insert(snap_id.clone(), summary);
+                }
+                None => missing.push(snap_id.clone()),
+            }
+        }
+
+        if !missing.is_empty() {
+            let mut fetched: HashMap<String, VoteSummary> =
+                fetch_by_snap_ids(&missing, options, conn)
+                    .await?
+                    .into_iter()
+                    .map(|s| (s.snap_id.clone(), s))
+                    .collect();
+
+            for snap_id in missing {
+                let summary = fetched
+                    .remove(&snap_id)
+                    .unwrap_or_else(|| VoteSummary::empty(&snap_id));
+                cache::vote_summaries().insert(snap_id.clone(), summary.clone());
+                summaries.insert(snap_id, summary);
+            }
+        }
+
+        Ok(snap_ids
+            .iter()
+            .map(|snap_id| {
+                summaries
+                    .get(snap_id)
+                    .cloned()
+                    .unwrap_or_else(|| VoteSummary::empty(snap_id))
+            })
+            .collect())
+    }
+
+    /// A summary for a snap that has received no votes.
+    fn empty(snap_id: &str) -> VoteSummary {
+        VoteSummary {
+            snap_id: snap_id.to_string(),
+            total_votes: 0,
+            positive_votes: 0,
+            decayed_total_votes: 0.0,
+            decayed_positive_votes: 0.0,
+        }
+    }
+
+    /// Retrieves a vote summary for each revision of the given snap that has received votes,
+    /// ordered from the most recent revision to the oldest.
+    pub async fn get_by_snap_id_per_revision(
+        snap_id: &str,
+        options: SummaryOptions,
+        conn: &mut PgConnection,
+    ) -> Result<Vec<RevisionVoteSummary>> {
+        let summaries = sqlx::query_as(&format!(
+            r#"
             SELECT
                 votes.snap_id,
+                votes.snap_revision,
                 COUNT(*) AS total_votes,
-                COUNT(*) FILTER (WHERE votes.vote_up) AS positive_votes
+                COUNT(*) FILTER (WHERE votes.vote_up) AS positive_votes,
+                COALESCE(
+                    SUM(POWER(0.5, LEAST(EXTRACT(EPOCH FROM NOW() - votes.created)::float8, 1000 * $2) / $2)),
+                    COUNT(*)
+                )::float8 AS decayed_total_votes,
+                COALESCE(
+                    SUM(POWER(0.5, LEAST(EXTRACT(EPOCH FROM NOW() - votes.created)::float8, 1000 * $2) / $2))
+                        FILTER (WHERE votes.vote_up),
+                    COUNT(*) FILTER (WHERE votes.vote_up)
+                )::float8 AS decayed_positive_votes
             FROM
-                votes",
+                {}
+            WHERE
+                votes.snap_id = $1
+            GROUP BY votes.snap_id, votes.snap_revision
+            ORDER BY votes.snap_revision DESC
+        "#,
+            options.votes_table()
+        ))
+        .bind(snap_id)
+        .bind(options.half_life_secs())
+        .fetch_all(conn)
+        .await?;
+
+        Ok(summaries)
+    }
+
+    /// Retrieves the vote summary for every snap matching the given [`VoteFilters`]
+    pub async fn get_for_filters(
+        filters: &VoteFilters,
+        options: SummaryOptions,
+        conn: &mut PgConnection,
+    ) -> Result<Vec<VoteSummary>> {
+        let mut builder = QueryBuilder::new("SELECT votes.snap_id,");
+        push_summary_columns(&mut builder, options);
+        builder.push(" FROM ").push(options.votes_table());
+        filters.push_to(&mut builder);
+
+        let summaries = builder.build_query_as().fetch_all(conn).await?;
+
+        Ok(summaries)
+    }
+
+    /// Retrieves a [`TrendingVoteSummary`] for every snap matching the given [`VoteFilters`],
+    /// comparing the votes cast within the timeframe of the filters against those cast in the
+    /// equally sized window immediately before it.
+    ///
+    /// The vote totals of the returned summaries cover both windows. If the timeframe of the
+    /// filters is unbounded there is nothing to compare against and no summaries are returned.
+    pub async fn get_trending_for_filters(
+        filters: &VoteFilters,
+        options: SummaryOptions,
+        conn: &mut PgConnection,
+    ) -> Result<Vec<TrendingVoteSummary>> {
+        let interval = match filters.timeframe.interval() {
+            Some(interval) => interval,
+            None => return Ok(Vec::new()),
+        };
+
+        let mut builder = QueryBuilder::new("SELECT votes.snap_id,");
+        push_summary_columns(&mut builder, options);
+        builder
+            .push(
+                r",
+                COUNT(*) FILTER (
+                    WHERE votes.vote_up AND votes.created >= NOW() - INTERVAL '",
+            )
+            .push(interval)
+            .push(
+                r"'
+                ) AS current_positive_votes,
+                COUNT(*) FILTER (
+                    WHERE votes.vote_up AND votes.created < NOW() - INTERVAL '",
+            )
+            .push(interval)
+            .push(
+                r"'
+                ) AS previous_positive_votes
+            FROM ",
+            )
+            .push(options.votes_table());
+        filters.push_clauses(&mut builder, 2);
+
+        let summaries = builder.build_query_as().fetch_all(conn).await?;
+
+        Ok(summaries)
+    }
+}
+
+/// A [`VoteSummary`] along with the positive votes a snap received in the current and previous
+/// windows of a [`Timeframe`], used to determine which snaps are trending.
+#[derive(Debug, Clone, FromRow)]
+pub struct TrendingVoteSummary {
+    /// The summary of votes across both windows.
+    #[sqlx(flatten)]
+    pub summary: VoteSummary,
+    /// The number of positive votes cast within the current window.
+    pub current_positive_votes: i64,
+    /// The number of positive votes cast within the previous window.
+    pub previous_positive_votes: i64,
+}
+
+/// Pushes the aggregate columns required to build a [`VoteSummary`] onto the given query.
+fn push_summary_columns(builder: &mut QueryBuilder<'_, Postgres>, options: SummaryOptions) {
+    builder
+        .push(
+            r"
+                COUNT(*) AS total_votes,
+                COUNT(*) FILTER (WHERE votes.vote_up) AS positive_votes,
+                COALESCE(
+                    SUM(POWER(0.5, LEAST(EXTRACT(EPOCH FROM NOW() - votes.created)::float8, 1000 * ",
+        )
+        .push_bind(options.half_life_secs())
+        .push(") / ")
+        .push_bind(options.half_life_secs())
+        .push(
+            r")),
+                    COUNT(*)
+                )::float8 AS decayed_total_votes,
+                COALESCE(
+                    SUM(POWER(0.5, LEAST(EXTRACT(EPOCH FROM NOW() - votes.created)::float8, 1000 * ",
+        )
+        .push_bind(options.half_life_secs())
+        .push(") / ")
+        .push_bind(options.half_life_secs())
+        .push(
+            r"))
+                        FILTER (WHERE votes.vote_up),
+                    COUNT(*) FILTER (WHERE votes.vote_up)
+                )::float8 AS decayed_positive_votes",
         );
+}
 
-        builder.push(match timeframe {
-            Timeframe::Week => " WHERE votes.created >= NOW() - INTERVAL '1 week'",
-            Timeframe::Month => " WHERE votes.created >= NOW() - INTERVAL '1 month'",
-            Timeframe::Unspecified => "",
-        });
+/// A composable set of filters used to restrict which votes are included when summarising
+/// votes across all snaps.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct VoteFilters {
+    /// Only include votes cast within this [`Timeframe`]
+    pub timeframe: Timeframe,
+    /// Only include snaps within this [`Category`]
+    pub category: Option<Category>,
+    /// Only include snaps which have received at least this many (matching) votes
+    pub min_votes: Option<i64>,
+    /// Only include votes cast on this revision or later
+    pub min_revision: Option<u32>,
+    /// Only include votes cast on this revision or earlier
+    pub max_revision: Option<u32>,
+}
+
+impl VoteFilters {
+    /// Pushes the `WHERE`, `GROUP BY` and `HAVING` clauses for this set of filters onto a
+    /// query selecting from the `votes` table.
+    fn push_to(&self, builder: &mut QueryBuilder<'_, Postgres>) {
+        self.push_clauses(builder, 1)
+    }
+
+    /// As [`push_to`][Self::push_to] but with the timeframe extended to cover `windows`
+    /// consecutive timeframes ending now.
+    fn push_clauses(&self, builder: &mut QueryBuilder<'_, Postgres>, windows: u32) {
+        let mut clause = " WHERE ";
+        let mut next_clause = || std::mem::replace(&mut clause, " AND ");
 
-        if let Some(category) = category {
+        if let Some(interval) = self.timeframe.interval() {
             builder
+                .push(next_clause())
+                .push("votes.created >= NOW() - INTERVAL '")
+                .push(interval)
+                .push("'");
+            if windows > 1 {
+                builder.push(" * ").push(windows);
+            }
+        }
+
+        if let Some(category) = self.category {
+            builder
+                .push(next_clause())
                 .push(
-                    r" 
-                    WHERE votes.snap_id IN (
-                    SELECT snap_categories.snap_id FROM snap_categories 
+                    r"votes.snap_id IN (
+                    SELECT snap_categories.snap_id FROM snap_categories
                     WHERE snap_categories.category = ",
                 )
                 .push_bind(category)
                 .push(")");
         }
 
+        if let Some(min_revision) = self.min_revision {
+            builder
+                .push(next_clause())
+                .push("votes.snap_revision >= ")
+                .push_bind(min_revision as i32);
+        }
+
+        if let Some(max_revision) = self.max_revision {
+            builder
+                .push(next_clause())
+                .push("votes.snap_revision <= ")
+                .push_bind(max_revision as i32);
+        }
+
         builder.push(" GROUP BY votes.snap_id");
-        let summaries = builder.build_query_as().fetch_all(conn).await?;
 
-        Ok(summaries)
+        if let Some(min_votes) = self.min_votes {
+            builder.push(" HAVING COUNT(*) >= ").push_bind(min_votes);
+        }
     }
 }
 
-#[cfg_attr(not(feature = "skip_cache"), cached(
-    time = 86400, // 24 hours
-    sync_writes = true,
-    key = "String",
-    convert = r##"{String::from(snap_id)}"##,
-    result = true,
-))]
-async fn get_by_snap_id_cached(snap_id: &str, conn: &mut PgConnection) -> Result<VoteSummary> {
-    let result: Option<VoteSummary> = sqlx::query_as(
+async fn fetch_by_snap_id(
+    snap_id: &str,
+    options: SummaryOptions,
+    conn: &mut PgConnection,
+) -> Result<VoteSummary> {
+    let result: Option<VoteSummary> = sqlx::query_as(&format!(
         r#"
             SELECT
                 votes.snap_id,
                 COUNT(*) AS total_votes,
-                COUNT(*) FILTER (WHERE votes.vote_up) AS positive_votes
+                COUNT(*) FILTER (WHERE votes.vote_up) AS positive_votes,
+                COALESCE(
+                    SUM(POWER(0.5, LEAST(EXTRACT(EPOCH FROM NOW() - votes.created)::float8, 1000 * $2) / $2)),
+                    COUNT(*)
+                )::float8 AS decayed_total_votes,
+                COALESCE(
+                    SUM(POWER(0.5, LEAST(EXTRACT(EPOCH FROM NOW() - votes.created)::float8, 1000 * $2) / $2))
+                        FILTER (WHERE votes.vote_up),
+                    COUNT(*) FILTER (WHERE votes.vote_up)
+                )::float8 AS decayed_positive_votes
             FROM
-                votes
+                {}
             WHERE
                 votes.snap_id = $1
             GROUP BY votes.snap_id
         "#,
-    )
+        options.votes_table()
+    ))
     .bind(snap_id)
+    .bind(options.half_life_secs())
     .fetch_optional(conn)
     .await?;
 
-    let summary = result.unwrap_or_else(|| VoteSummary {
-        snap_id: snap_id.to_string(),
-        total_votes: 0,
-        positive_votes: 0,
-    });
+    Ok(result.unwrap_or_else(|| VoteSummary::empty(snap_id)))
+}
+
+async fn fetch_by_snap_ids(
+    snap_ids: &[String],
+    options: SummaryOptions,
+    conn: &mut PgConnection,
+) -> Result<Vec<VoteSummary>> {
+    let mut builder = QueryBuilder::new("SELECT votes.snap_id,");
+    push_summary_columns(&mut builder, options);
+    builder
+        .push(" FROM ")
+        .push(options.votes_table())
+        .push(" WHERE votes.snap_id = ANY(")
+        .push_bind(snap_ids)
+        .push(") GROUP BY votes.snap_id");
+
+    let summaries = builder.build_query_as().fetch_all(conn).await?;
+
+    Ok(summaries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use simple_test_case::test_case;
+
+    fn where_clause(filters: VoteFilters) -> String {
+        let mut builder = QueryBuilder::new("SELECT * FROM votes");
+        filters.push_to(&mut builder);
+
+        // Normalise whitespace so that we can ignore the formatting of the raw SQL strings
+        builder
+            .sql()
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    #[test]
+    fn no_filters_only_groups() {
+        assert_eq!(
+            where_clause(VoteFilters::default()),
+            "SELECT * FROM votes GROUP BY votes.snap_id"
+        );
+    }
 
-    Ok(summary)
+    #[test_case(Timeframe::Unspecified, None; "unspecified")]
+    #[test_case(Timeframe::AllTime, None; "all time")]
+    #[test_case(Timeframe::Day, Some("1 day"); "day")]
+    #[test_case(Timeframe::Week, Some("1 week"); "week")]
+    #[test_case(Timeframe::Month, Some("1 month"); "month")]
+    #[test_case(Timeframe::Quarter, Some("3 months"); "quarter")]
+    #[test_case(Timeframe::Year, Some("1 year"); "year")]
+    #[test]
+    fn timeframe_intervals(timeframe: Timeframe, expected: Option<&str>) {
+        assert_eq!(timeframe.interval(), expected);
+    }
+
+    #[test]
+    fn all_filters_are_combined_with_a_single_where() {
+        let sql = where_clause(VoteFilters {
+            timeframe: Timeframe::Week,
+            category: Some(Category::Games),
+            min_votes: Some(10),
+            min_revision: Some(2),
+            max_revision: Some(5),
+        });
+
+        assert_eq!(sql.matches("WHERE").count(), 2, "{sql}"); // including the category subquery
+        assert_eq!(
+            sql,
+            "SELECT * FROM votes \
+             WHERE votes.created >= NOW() - INTERVAL '1 week' \
+             AND votes.snap_id IN ( SELECT snap_categories.snap_id FROM snap_categories \
+             WHERE snap_categories.category = $1) \
+             AND votes.snap_revision >= $2 \
+             AND votes.snap_revision <= $3 \
+             GROUP BY votes.snap_id HAVING COUNT(*) >= $4"
+        );
+    }
+
+    #[test]
+    fn timeframe_can_cover_multiple_windows() {
+        let mut builder = QueryBuilder::new("SELECT * FROM votes");
+        VoteFilters {
+            timeframe: Timeframe::Month,
+            ..Default::default()
+        }
+        .push_clauses(&mut builder, 2);
+
+        assert_eq!(
+            builder.sql(),
+            "SELECT * FROM votes WHERE votes.created >= NOW() - INTERVAL '1 month' * 2 \
+             GROUP BY votes.snap_id"
+        );
+    }
+
+    #[test]
+    fn category_without_timeframe_starts_the_where_clause() {
+        let sql = where_clause(VoteFilters {
+            category: Some(Category::Games),
+            ..Default::default()
+        });
+
+        assert!(
+            sql.starts_with("SELECT * FROM votes WHERE votes.snap_id IN ("),
+            "{sql}"
+        );
+    }
 }
@@ -0,0 +1,83 @@
+//! Flagging suspicious votes so that they can be excluded when rating snaps.
+use crate::{cache, db::Result, Config};
+use sqlx::PgConnection;
+use std::time::Duration;
+
+/// The reason recorded against votes flagged by [`flag_vote_bursts`]
+pub const BURST_FROM_NEW_USERS: &str = "burst_from_new_users";
+
+/// Settings for detecting bursts of votes on a single snap from newly created users, as seen
+/// when a campaign registers many clients to vote a snap up or down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BurstDetection {
+    /// How far back to look for votes on the snap
+    pub window: Duration,
+    /// Votes cast by users created less than this long before voting are counted
+    pub new_user_max_age: Duration,
+    /// The number of votes from new users within the window at which they are all flagged, or
+    /// zero to disable detection
+    pub threshold: i64,
+}
+
+impl Default for BurstDetection {
+    fn default() -> Self {
+        Self {
+            window: Duration::from_secs(60 * 60),
+            new_user_max_age: Duration::from_secs(24 * 60 * 60),
+            threshold: 20,
+        }
+    }
+}
+
+impl BurstDetection {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            window: Duration::from_secs(config.fraud_burst_window_secs),
+            new_user_max_age: Duration::from_secs(config.fraud_burst_new_user_max_age_secs),
+            threshold: config.fraud_burst_threshold,
+        }
+    }
+}
+
+/// Flag the recent votes on a snap from new users if there are enough of them to count as a
+/// burst, returning the number of newly flagged votes.
+pub async fn flag_vote_bursts(
+    snap_id: &str,
+    detection: BurstDetection,
+    conn: &mut PgConnection,
+) -> Result<u64> {
+    if detection.threshold <= 0 {
+        return Ok(0);
+    }
+
+    let result = sqlx::query(
+        r#"
+        WITH suspicious AS (
+            SELECT votes.id
+            FROM votes
+            INNER JOIN users ON users.id = votes.user_id_fk
+            WHERE votes.snap_id = $1
+            AND votes.created >= NOW() - make_interval(secs => $2)
+            AND votes.created - users.created <= make_interval(secs => $3)
+        )
+        INSERT INTO vote_flags (vote_id, reason)
+        SELECT id, $4 FROM suspicious
+        WHERE (SELECT COUNT(*) FROM suspicious) >= $5
+        ON CONFLICT (vote_id) DO NOTHING
+        "#,
+    )
+    .bind(snap_id)
+    .bind(detection.window.as_secs_f64())
+    .bind(detection.new_user_max_age.as_secs_f64())
+    .bind(BURST_FROM_NEW_USERS)
+    .bind(detection.threshold)
+    .execute(conn)
+    .await?;
+
+    let flagged = result.rows_affected();
+    if flagged > 0 {
+        cache::invalidate_snap(snap_id);
+    }
+
+    Ok(flagged)
+}
@@ -0,0 +1,279 @@
+use crate::{
+    conn,
+    db::{BannedClient, ContentReport, ReportState, SummaryOptions, Vote, VoteStats, VoteSummary},
+    grpc::{decode_id_cursor, encode_id_cursor, to_timestamp, user::EXPECTED_CLIENT_HASH_LENGTH},
+    proto::admin::{
+        admin_server::{self, AdminServer},
+        BanClientRequest, BanClientResponse, GetVoteStatsRequest, GetVoteStatsResponse,
+        ListReportsRequest, ListReportsResponse, RefreshCategoriesRequest,
+        RefreshCategoriesResponse, RemoveVotesRequest, RemoveVotesResponse, Report as PbReport,
+        ResolveReportRequest, ResolveReportResponse, RevisionVoteStats, UnbanClientRequest,
+        UnbanClientResponse,
+    },
+    ratings::refresh_categories_for_snap,
+    Context,
+};
+use std::sync::Arc;
+use tonic::{Request, Response, Status};
+use tracing::{error, info};
+
+/// Moderation RPCs, only accessible with an admin token (see [`AuthMiddleware`]).
+///
+/// [`AuthMiddleware`]: crate::middleware::AuthMiddleware
+#[derive(Clone)]
+pub struct AdminService {
+    ctx: Arc<Context>,
+}
+
+impl AdminService {
+    pub fn new_server(ctx: Arc<Context>) -> AdminServer<AdminService> {
+        AdminServer::new(Self { ctx })
+    }
+}
+
+#[tonic::async_trait]
+impl admin_server::Admin for AdminService {
+    async fn remove_votes(
+        &self,
+        request: Request<RemoveVotesRequest>,
+    ) -> Result<Response<RemoveVotesResponse>, Status> {
+        let RemoveVotesRequest {
+            snap_id,
+            client_hash,
+        } = request.into_inner();
+        if snap_id.is_empty() {
+            return Err(Status::invalid_argument("snap id"));
+        }
+
+        match Vote::delete_for_snap(&snap_id, client_hash.as_deref(), conn!()).await {
+            Ok(removed_votes) => {
+                info!(%snap_id, ?client_hash, removed_votes, "admin removed votes");
+                Ok(Response::new(RemoveVotesResponse { removed_votes }))
+            }
+
+            Err(e) => {
+                error!("Error in remove_votes: {:?}", e);
+                Err(Status::unknown("Internal server error"))
+            }
+        }
+    }
+
+    async fn ban_client(
+        &self,
+        request: Request<BanClientRequest>,
+    ) -> Result<Response<BanClientResponse>, Status> {
+        let BanClientRequest {
+            client_hash,
+            reason,
+            remove_votes,
+        } = request.into_inner();
+        if client_hash.len() != EXPECTED_CLIENT_HASH_LENGTH {
+            return Err(Status::invalid_argument("client hash"));
+        }
+
+        match BannedClient::ban(&client_hash, &reason, remove_votes, conn!()).await {
+            Ok(removed_votes) => {
+                info!(%client_hash, %reason, removed_votes, "admin banned client");
+                Ok(Response::new(BanClientResponse { removed_votes }))
+            }
+
+            Err(e) => {
+                error!("Error in ban_client: {:?}", e);
+                Err(Status::unknown("Internal server error"))
+            }
+        }
+    }
+
+    async fn unban_client(
+        &self,
+        request: Request<UnbanClientRequest>,
+    ) -> Result<Response<UnbanClientResponse>, Status> {
+        let UnbanClientRequest { client_hash } = request.into_inner();
+
+        match BannedClient::unban(&client_hash, conn!()).await {
+            Ok(unbanned) => {
+                info!(%client_hash, unbanned, "admin unbanned client");
+                Ok(Response::new(UnbanClientResponse { unbanned }))
+            }
+
+            Err(e) => {
+                error!("Error in unban_client: {:?}", e);
+                Err(Status::unknown("Internal server error"))
+            }
+        }
+    }
+
+    async fn refresh_categories(
+        &self,
+        request: Request<RefreshCategoriesRequest>,
+    ) -> Result<Response<RefreshCategoriesResponse>, Status> {
+        let RefreshCategoriesRequest { snap_id } = request.into_inner();
+        if snap_id.is_empty() {
+            return Err(Status::invalid_argument("snap id"));
+        }
+
+        match refresh_categories_for_snap(&snap_id, &self.ctx).await {
+            Ok(Some(categories)) => Ok(Response::new(RefreshCategoriesResponse {
+                categories: categories.into_iter().map(|c| c as i32).collect(),
+            })),
+
+            Ok(None) => Err(Status::not_found("snap not found")),
+
+            Err(e) => {
+                error!(%snap_id, "unable to refresh snap categories: {e}");
+                Err(Status::unavailable(
+                    "unable to fetch categories from snapcraft.io",
+                ))
+            }
+        }
+    }
+
+    async fn get_vote_stats(
+        &self,
+        request: Request<GetVoteStatsRequest>,
+    ) -> Result<Response<GetVoteStatsResponse>, Status> {
+        let GetVoteStatsRequest { snap_id } = request.into_inner();
+        if snap_id.is_empty() {
+            return Err(Status::invalid_argument("snap id"));
+        }
+
+        let conn = conn!();
+        let stats = VoteStats::get_by_snap_id(&snap_id, conn).await;
+        // Raw counts, so decay is deliberately left disabled
+        let revisions =
+            VoteSummary::get_by_snap_id_per_revision(&snap_id, SummaryOptions::default(), conn)
+                .await;
+
+        match (stats, revisions) {
+            (Ok(stats), Ok(revisions)) => Ok(Response::new(GetVoteStatsResponse {
+                snap_id,
+                total_votes: stats.total_votes as u64,
+                positive_votes: stats.positive_votes as u64,
+                unique_voters: stats.unique_voters as u64,
+                flagged_votes: stats.flagged_votes as u64,
+                first_vote: stats.first_vote.map(to_timestamp),
+                last_vote: stats.last_vote.map(to_timestamp),
+                revisions: revisions
+                    .into_iter()
+                    .map(|r| RevisionVoteStats {
+                        snap_revision: r.snap_revision as i32,
+                        total_votes: r.summary.total_votes as u64,
+                        positive_votes: r.summary.positive_votes as u64,
+                    })
+                    .collect(),
+            })),
+
+            (Err(e), _) | (_, Err(e)) => {
+                error!("Error in get_vote_stats: {:?}", e);
+                Err(Status::unknown("Internal server error"))
+            }
+        }
+    }
+
+    async fn list_reports(
+        &self,
+        request: Request<ListReportsRequest>,
+    ) -> Result<Response<ListReportsResponse>, Status> {
+        let ListReportsRequest {
+            state,
+            page_size,
+            page_token,
+        } = request.into_inner();
+
+        let state = match state {
+            Some(s) => {
+                Some(ReportState::from_repr(s).ok_or(Status::invalid_argument("invalid state"))?)
+            }
+            None => None,
+        };
+
+        let page_size = match page_size {
+            Some(0) | None => self.ctx.config.review_default_page_size,
+            Some(n) => n.min(self.ctx.config.review_max_page_size),
+        } as usize;
+
+        let after = if page_token.is_empty() {
+            None
+        } else {
+            Some(decode_id_cursor(&page_token)?)
+        };
+
+        // Fetch one extra report to find out whether there is another page
+        let limit = page_size as i64 + 1;
+        match ContentReport::list(state, after, limit, conn!()).await {
+            Ok(mut reports) => {
+                let next_page_token = if reports.len() > page_size {
+                    reports.truncate(page_size);
+                    reports
+                        .last()
+                        .map(|r| encode_id_cursor(r.id))
+                        .unwrap_or_default()
+                } else {
+                    String::new()
+                };
+
+                Ok(Response::new(ListReportsResponse {
+                    reports: reports.into_iter().map(PbReport::from).collect(),
+                    next_page_token,
+                }))
+            }
+
+            Err(e) => {
+                error!("Error in list_reports: {:?}", e);
+                Err(Status::unknown("Internal server error"))
+            }
+        }
+    }
+
+    async fn resolve_report(
+        &self,
+        request: Request<ResolveReportRequest>,
+    ) -> Result<Response<ResolveReportResponse>, Status> {
+        let ResolveReportRequest {
+            report_id,
+            resolution,
+        } = request.into_inner();
+
+        let resolution = match ReportState::from_repr(resolution) {
+            Some(r @ (ReportState::Dismissed | ReportState::Actioned)) => r,
+            _ => return Err(Status::invalid_argument("resolution")),
+        };
+
+        match ContentReport::resolve(report_id, resolution, conn!()).await {
+            Ok(0) => Err(Status::not_found("no open report with that id")),
+
+            Ok(resolved_reports) => {
+                info!(
+                    report_id,
+                    ?resolution,
+                    resolved_reports,
+                    "admin resolved report"
+                );
+                Ok(Response::new(ResolveReportResponse { resolved_reports }))
+            }
+
+            Err(e) => {
+                error!("Error in resolve_report: {:?}", e);
+                Err(Status::unknown("Internal server error"))
+            }
+        }
+    }
+}
+
+impl From<ContentReport> for PbReport {
+    fn from(value: ContentReport) -> Self {
+        Self {
+            id: value.id,
+            review_id: value.review_id,
+            snap_id: value.snap_id,
+            review_body: value.review_body,
+            author_client_hash: value.author_client_hash,
+            reporter_client_hash: value.reporter_client_hash,
+            reason: value.reason as i32,
+            details: value.details,
+            state: value.state as i32,
+            created: Some(to_timestamp(value.created)),
+            resolved_at: value.resolved_at.map(to_timestamp),
+        }
+    }
+}
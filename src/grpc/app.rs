@@ -1,17 +1,26 @@
 use crate::{
     conn,
-    db::VoteSummary,
+    db::{Review, RevisionVoteSummary, VoteReasonCount, VoteSummary},
+    grpc::{decode_id_cursor, encode_id_cursor, to_timestamp},
     proto::{
         app::{
             app_server::{App, AppServer},
-            GetRatingRequest, GetRatingResponse,
+            GetFeedbackBreakdownRequest, GetFeedbackBreakdownResponse, GetRatingByRevisionRequest,
+            GetRatingByRevisionResponse, GetRatingRequest, GetRatingResponse, GetRatingsRequest,
+            GetRatingsResponse, ListReviewsRequest, ListReviewsResponse,
+            PublisherReply as PbPublisherReply, ReasonCount as PbReasonCount, Review as PbReview,
+            RevisionFeedback as PbRevisionFeedback, RevisionRating as PbRevisionRating,
         },
         common::Rating as PbRating,
     },
-    ratings::{get_snap_name, Rating},
+    ratings::{get_snap_name, get_snap_names, Rating, RatingCalculator},
     Context,
 };
-use std::{error::Error, sync::Arc};
+use std::{
+    collections::{BTreeMap, HashSet},
+    error::Error,
+    sync::Arc,
+};
 use tonic::{Request, Response, Status};
 use tracing::error;
 
@@ -38,30 +47,31 @@ impl App for RatingService {
             return Err(Status::invalid_argument("snap id"));
         }
 
-        match VoteSummary::get_by_snap_id(&snap_id, conn!()).await {
+        // Bound separately so that the connection is released before looking up the snap name
+        let summary =
+            VoteSummary::get_by_snap_id(&snap_id, self.ctx.summary_options, conn!()).await;
+        match summary {
             Ok(votes) => {
                 let Rating {
                     snap_id,
                     total_votes,
                     ratings_band,
-                } = Rating::from(votes);
-
-                let snap_name = get_snap_name(
-                    &snap_id,
-                    &self.ctx.config.snapcraft_io_uri,
-                    &self.ctx.http_client,
-                )
-                .await
-                .map_err(|e| {
-                    let mut err = &e as &dyn Error;
-                    let mut error = format!("{err}");
-                    while let Some(src) = err.source() {
-                        error.push_str(&format!("\n\nCaused by: {src}"));
-                        err = src;
-                    }
-                    error!(%error, "unable to fetch snap name");
-                    Status::unknown("Internal server error")
-                })?;
+                    decayed_ratings_band,
+                } = Rating::from_summary(votes, &self.ctx.rating_calculator);
+
+                let snap_name = get_snap_name(&snap_id, &self.ctx)
+                    .await
+                    .map(Option::unwrap_or_default)
+                    .map_err(|e| {
+                        let mut err = &e as &dyn Error;
+                        let mut error = format!("{err}");
+                        while let Some(src) = err.source() {
+                            error.push_str(&format!("\n\nCaused by: {src}"));
+                            err = src;
+                        }
+                        error!(%error, "unable to fetch snap name");
+                        Status::unknown("Internal server error")
+                    })?;
 
                 Ok(Response::new(GetRatingResponse {
                     rating: Some(PbRating {
@@ -69,6 +79,7 @@ impl App for RatingService {
                         total_votes,
                         ratings_band: ratings_band as i32,
                         snap_name,
+                        decayed_ratings_band: decayed_ratings_band as i32,
                     }),
                 }))
             }
@@ -79,4 +90,258 @@ impl App for RatingService {
             }
         }
     }
+
+    async fn get_ratings(
+        &self,
+        request: Request<GetRatingsRequest>,
+    ) -> Result<Response<GetRatingsResponse>, Status> {
+        let GetRatingsRequest { snap_ids } = request.into_inner();
+        // Checked before removing duplicates so that oversized requests are turned away cheaply
+        if snap_ids.len() > self.ctx.config.rating_max_batch_size {
+            return Err(Status::invalid_argument(format!(
+                "at most {} snap ids may be requested at once",
+                self.ctx.config.rating_max_batch_size
+            )));
+        }
+        if snap_ids.is_empty() || snap_ids.iter().any(String::is_empty) {
+            return Err(Status::invalid_argument("snap ids"));
+        }
+
+        let mut seen = HashSet::with_capacity(snap_ids.len());
+        let snap_ids: Vec<String> = snap_ids
+            .into_iter()
+            .filter(|id| seen.insert(id.clone()))
+            .collect();
+
+        // Bound separately so that the connection is released before looking up snap names, which
+        // may need to wait on snapcraft.io
+        let summaries =
+            VoteSummary::get_by_snap_ids(&snap_ids, self.ctx.summary_options, conn!()).await;
+        let summaries = match summaries {
+            Ok(summaries) => summaries,
+            Err(e) => {
+                error!("Error calling get_by_snap_ids: {:?}", e);
+                return Err(Status::unknown("Internal server error"));
+            }
+        };
+
+        let mut names = get_snap_names(&snap_ids, &self.ctx).await.map_err(|e| {
+            error!("unable to fetch snap names: {e}");
+            Status::unknown("Internal server error")
+        })?;
+
+        let ratings = summaries
+            .into_iter()
+            .map(|summary| {
+                let Rating {
+                    snap_id,
+                    total_votes,
+                    ratings_band,
+                    decayed_ratings_band,
+                } = Rating::from_summary(summary, &self.ctx.rating_calculator);
+
+                PbRating {
+                    snap_name: names.remove(&snap_id).unwrap_or_default(),
+                    snap_id,
+                    total_votes,
+                    ratings_band: ratings_band as i32,
+                    decayed_ratings_band: decayed_ratings_band as i32,
+                }
+            })
+            .collect();
+
+        Ok(Response::new(GetRatingsResponse { ratings }))
+    }
+
+    async fn get_rating_by_revision(
+        &self,
+        request: Request<GetRatingByRevisionRequest>,
+    ) -> Result<Response<GetRatingByRevisionResponse>, Status> {
+        let GetRatingByRevisionRequest { snap_id } = request.into_inner();
+        if snap_id.is_empty() {
+            return Err(Status::invalid_argument("snap id"));
+        }
+
+        let options = self.ctx.summary_options;
+        match VoteSummary::get_by_snap_id_per_revision(&snap_id, options, conn!()).await {
+            Ok(summaries) => {
+                let revisions = summaries
+                    .into_iter()
+                    .map(|s| PbRevisionRating::from_summary(s, &self.ctx.rating_calculator))
+                    .collect();
+
+                Ok(Response::new(GetRatingByRevisionResponse {
+                    snap_id,
+                    revisions,
+                }))
+            }
+
+            Err(e) => {
+                error!("Error calling get_by_snap_id_per_revision: {:?}", e);
+                Err(Status::unknown("Internal server error"))
+            }
+        }
+    }
+
+    async fn list_reviews(
+        &self,
+        request: Request<ListReviewsRequest>,
+    ) -> Result<Response<ListReviewsResponse>, Status> {
+        let ListReviewsRequest {
+            snap_id,
+            snap_revision,
+            page_size,
+            page_token,
+        } = request.into_inner();
+        if snap_id.is_empty() {
+            return Err(Status::invalid_argument("snap id"));
+        }
+
+        let page_size = match page_size {
+            Some(0) | None => self.ctx.config.review_default_page_size,
+            Some(n) => n.min(self.ctx.config.review_max_page_size),
+        } as usize;
+
+        let after = if page_token.is_empty() {
+            None
+        } else {
+            Some(decode_id_cursor(&page_token)?)
+        };
+
+        // Fetch one extra review to find out whether there is another page
+        let revision = snap_revision.map(|r| r as u32);
+        let limit = page_size as i64 + 1;
+        match Review::get_for_snap(&snap_id, revision, after, limit, conn!()).await {
+            Ok(mut reviews) => {
+                let next_page_token = if reviews.len() > page_size {
+                    reviews.truncate(page_size);
+                    reviews
+                        .last()
+                        .map(|r| encode_id_cursor(r.id))
+                        .unwrap_or_default()
+                } else {
+                    String::new()
+                };
+
+                Ok(Response::new(ListReviewsResponse {
+                    reviews: reviews.into_iter().map(PbReview::from).collect(),
+                    next_page_token,
+                }))
+            }
+
+            Err(e) => {
+                error!("Error in list_reviews: {:?}", e);
+                Err(Status::unknown("Internal server error"))
+            }
+        }
+    }
+
+    async fn get_feedback_breakdown(
+        &self,
+        request: Request<GetFeedbackBreakdownRequest>,
+    ) -> Result<Response<GetFeedbackBreakdownResponse>, Status> {
+        let GetFeedbackBreakdownRequest { snap_id } = request.into_inner();
+        if snap_id.is_empty() {
+            return Err(Status::invalid_argument("snap id"));
+        }
+
+        let options = self.ctx.summary_options;
+        match VoteReasonCount::get_by_snap_id_per_revision(&snap_id, options, conn!()).await {
+            Ok(counts) => {
+                let (reasons, revisions) = feedback_breakdown(counts);
+
+                Ok(Response::new(GetFeedbackBreakdownResponse {
+                    snap_id,
+                    reasons,
+                    revisions,
+                }))
+            }
+
+            Err(e) => {
+                error!("Error in get_feedback_breakdown: {:?}", e);
+                Err(Status::unknown("Internal server error"))
+            }
+        }
+    }
+}
+
+/// Group per-revision reason counts, which arrive ordered by revision, by revision and total
+/// them across all revisions.
+fn feedback_breakdown(
+    counts: Vec<VoteReasonCount>,
+) -> (Vec<PbReasonCount>, Vec<PbRevisionFeedback>) {
+    let mut totals = BTreeMap::new();
+    let mut revisions: Vec<PbRevisionFeedback> = Vec::new();
+
+    for count in counts {
+        *totals.entry(count.reason).or_insert(0) += count.votes as u64;
+
+        let reason = PbReasonCount {
+            reason: count.reason as i32,
+            votes: count.votes as u64,
+        };
+        match revisions.last_mut() {
+            Some(r) if r.snap_revision == count.snap_revision as i32 => r.reasons.push(reason),
+            _ => revisions.push(PbRevisionFeedback {
+                snap_revision: count.snap_revision as i32,
+                reasons: vec![reason],
+            }),
+        }
+    }
+
+    let totals = totals
+        .into_iter()
+        .map(|(reason, votes)| PbReasonCount {
+            reason: reason as i32,
+            votes,
+        })
+        .collect();
+
+    (totals, revisions)
+}
+
+impl From<Review> for PbReview {
+    fn from(value: Review) -> Self {
+        let reply = match (
+            value.reply_publisher_id,
+            value.reply_body,
+            value.reply_created,
+            value.reply_updated,
+        ) {
+            (Some(publisher_id), Some(body), Some(created), Some(updated)) => {
+                Some(PbPublisherReply {
+                    publisher_id,
+                    body,
+                    created: Some(to_timestamp(created)),
+                    updated: Some(to_timestamp(updated)),
+                })
+            }
+            _ => None,
+        };
+
+        Self {
+            id: value.id,
+            snap_id: value.snap_id,
+            snap_revision: value.snap_revision as i32,
+            body: value.body,
+            created: Some(to_timestamp(value.created)),
+            updated: Some(to_timestamp(value.updated)),
+            vote_up: value.vote_up,
+            reply,
+        }
+    }
+}
+
+impl PbRevisionRating {
+    fn from_summary(value: RevisionVoteSummary, calculator: &RatingCalculator) -> Self {
+        let (raw_rating, ratings_band) = calculator.calculate_band(&value.summary);
+
+        Self {
+            snap_revision: value.snap_revision as i32,
+            total_votes: value.summary.total_votes as u64,
+            positive_votes: value.summary.positive_votes as u64,
+            raw_rating: raw_rating.unwrap_or(0.0) as f32,
+            ratings_band: ratings_band as i32,
+        }
+    }
 }
@@ -1,6 +1,6 @@
 use crate::{
-    conn,
-    db::{Category, Timeframe, VoteSummary},
+    cache, conn,
+    db::{Category, SummaryOptions, Timeframe, VoteFilters, VoteSummary},
     proto::{
         chart::{
             chart_server::{self, ChartServer},
@@ -8,11 +8,12 @@ use crate::{
         },
         common::{Rating as PbRating, RatingsBand as PbRatingsBand},
     },
-    ratings::{get_snap_name, Chart, ChartData, Error, Rating, RatingsBand},
+    ratings::{
+        get_snap_names, Chart, ChartCursor, ChartData, ChartType, Rating, RatingCalculator,
+        RatingsBand,
+    },
     Context,
 };
-use cached::proc_macro::cached;
-use futures::future::try_join_all;
 use std::sync::Arc;
 use tonic::{Request, Response, Status};
 use tracing::error;
@@ -37,6 +38,12 @@ impl chart_server::Chart for ChartService {
         let GetChartRequest {
             timeframe,
             category,
+            page_size,
+            page_token,
+            min_votes,
+            min_revision,
+            max_revision,
+            chart_type,
         } = request.into_inner();
 
         let category = match category {
@@ -46,9 +53,61 @@ impl chart_server::Chart for ChartService {
             None => None,
         };
 
-        let timeframe = Timeframe::from_repr(timeframe).unwrap_or(Timeframe::Unspecified);
+        let chart_type = ChartType::from_repr(chart_type)
+            .ok_or(Status::invalid_argument("invalid chart type"))?;
+
+        let timeframe = match (chart_type, Timeframe::from_repr(timeframe)) {
+            (ChartType::Trending, Some(Timeframe::Unspecified) | None) => Timeframe::Week,
+            (ChartType::Trending, Some(Timeframe::AllTime)) => {
+                return Err(Status::invalid_argument(
+                    "trending charts require a bounded timeframe",
+                ))
+            }
+            (_, timeframe) => timeframe.unwrap_or(Timeframe::Unspecified),
+        };
 
-        let chart = get_chart_cached(category, timeframe).await;
+        let revision = |r: Option<i32>| match r {
+            Some(r) if r <= 0 => Err(Status::invalid_argument("invalid snap revision")),
+            r => Ok(r.map(|r| r as u32)),
+        };
+        let (min_revision, max_revision) = (revision(min_revision)?, revision(max_revision)?);
+        if let (Some(min), Some(max)) = (min_revision, max_revision) {
+            if min > max {
+                return Err(Status::invalid_argument(
+                    "min revision must not be greater than max revision",
+                ));
+            }
+        }
+
+        let filters = VoteFilters {
+            timeframe,
+            category,
+            min_votes: min_votes.map(Into::into),
+            min_revision,
+            max_revision,
+        };
+
+        let page_size = match page_size {
+            Some(0) | None => self.ctx.config.chart_default_page_size,
+            Some(n) => n.min(self.ctx.config.chart_max_page_size),
+        };
+
+        let cursor = if page_token.is_empty() {
+            None
+        } else {
+            Some(
+                ChartCursor::decode(&page_token)
+                    .ok_or(Status::invalid_argument("invalid page token"))?,
+            )
+        };
+
+        let chart = get_chart_cached(
+            chart_type,
+            filters,
+            self.ctx.summary_options,
+            &self.ctx.rating_calculator,
+        )
+        .await;
 
         match chart {
             Ok(chart) if chart.data.is_empty() => {
@@ -56,26 +115,30 @@ impl chart_server::Chart for ChartService {
             }
 
             Ok(chart) => {
-                let ordered_chart_data: Vec<PbChartData> =
-                    try_join_all(chart.data.into_iter().map(|chart_data| async {
-                        let snap_name = get_snap_name(
-                            &chart_data.rating.snap_id,
-                            &self.ctx.config.snapcraft_io_uri,
-                            &self.ctx.http_client,
-                        )
-                        .await?;
-
-                        Result::<PbChartData, Error>::Ok(
-                            PbChartData::from_chart_data_and_snap_name(chart_data, snap_name),
-                        )
-                    }))
-                    .await
-                    .map_err(|_| Status::unknown("Internal server error"))?;
+                let (page, next_cursor) = chart.into_page(page_size as usize, cursor.as_ref());
+
+                let snap_ids: Vec<String> = page.iter().map(|d| d.rating.snap_id.clone()).collect();
+                let mut snap_names = get_snap_names(&snap_ids, &self.ctx).await.map_err(|e| {
+                    error!("unable to fetch snap names: {e}");
+                    Status::unknown("Internal server error")
+                })?;
+
+                let ordered_chart_data: Vec<PbChartData> = page
+                    .into_iter()
+                    .map(|chart_data| {
+                        let snap_name = snap_names
+                            .remove(&chart_data.rating.snap_id)
+                            .unwrap_or_default();
+                        PbChartData::from_chart_data_and_snap_name(chart_data, snap_name)
+                    })
+                    .collect();
 
                 let payload = GetChartResponse {
                     timeframe: timeframe as i32,
                     category: category.map(|c| c as i32),
                     ordered_chart_data,
+                    next_page_token: next_cursor.map(|c| c.encode()).unwrap_or_default(),
+                    chart_type: chart_type as i32,
                 };
 
                 Ok(Response::new(payload))
@@ -89,20 +152,32 @@ impl chart_server::Chart for ChartService {
     }
 }
 
-#[cfg_attr(not(feature = "skip_cache"), cached(
-    time = 86400, // 24 hours
-    sync_writes = true,
-    key = "String",
-    convert = r##"{format!("{:?}{:?}", category, timeframe)}"##,
-    result = true,
-))]
 async fn get_chart_cached(
-    category: Option<Category>,
-    timeframe: Timeframe,
+    chart_type: ChartType,
+    filters: VoteFilters,
+    options: SummaryOptions,
+    calculator: &RatingCalculator,
 ) -> Result<Chart, crate::db::Error> {
-    let summaries = VoteSummary::get_for_timeframe(timeframe, category, conn!()).await?;
+    let key = format!("{:?}{:?}", chart_type, filters);
+    if let Some(chart) = cache::charts().get(&key) {
+        return Ok(chart);
+    }
+
+    let conn = conn!();
+    let chart = match chart_type {
+        ChartType::TopRated => {
+            let summaries = VoteSummary::get_for_filters(&filters, options, conn).await?;
+            Chart::new(filters.timeframe, summaries, calculator)
+        }
+
+        ChartType::Trending => {
+            let summaries = VoteSummary::get_trending_for_filters(&filters, options, conn).await?;
+            Chart::trending(filters.timeframe, summaries, calculator)
+        }
+    };
+    cache::charts().insert(key, chart.clone());
 
-    Ok(Chart::new(timeframe, summaries))
+    Ok(chart)
 }
 
 impl PbChartData {
@@ -124,6 +199,7 @@ impl PbRating {
             total_votes: rating.total_votes,
             ratings_band: rating.ratings_band as i32,
             snap_name,
+            decayed_ratings_band: rating.decayed_ratings_band as i32,
         }
     }
 }
@@ -134,6 +210,7 @@ impl From<PbRating> for Rating {
             snap_id: r.snap_id,
             total_votes: r.total_votes,
             ratings_band: RatingsBand::from_repr(r.ratings_band).unwrap(),
+            decayed_ratings_band: RatingsBand::from_repr(r.decayed_ratings_band).unwrap(),
         }
     }
 }
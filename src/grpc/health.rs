@@ -0,0 +1,71 @@
+//! Reporting the health of the service via the standard `grpc.health.v1.Health` service
+use crate::{
+    db::check_db_conn,
+    proto::{
+        admin::admin_server::AdminServer, app::app_server::AppServer,
+        chart::chart_server::ChartServer, publisher::publisher_server::PublisherServer,
+        user::user_server::UserServer,
+    },
+};
+use std::time::Duration;
+use tokio::time::timeout;
+use tonic::server::NamedService;
+use tonic_health::{server::HealthReporter, ServingStatus};
+use tracing::{error, info};
+
+use super::{
+    admin::AdminService, app::RatingService, charts::ChartService, publisher::PublisherService,
+    user::UserService,
+};
+
+/// The services we report health for, the empty name being the status of the server as a whole
+const SERVICES: [&str; 6] = [
+    "",
+    <AdminServer<AdminService> as NamedService>::NAME,
+    <AppServer<RatingService> as NamedService>::NAME,
+    <ChartServer<ChartService> as NamedService>::NAME,
+    <PublisherServer<PublisherService> as NamedService>::NAME,
+    <UserServer<UserService> as NamedService>::NAME,
+];
+
+/// Set the health status for all of our services.
+pub async fn set_status(reporter: &mut HealthReporter, status: ServingStatus) {
+    for service in SERVICES {
+        reporter.set_service_status(service, status).await;
+    }
+}
+
+/// Periodically check that we are able to reach the DB, reporting all services as NOT_SERVING
+/// while it is unavailable. A check that takes longer than the interval counts as a failure.
+pub async fn monitor_db_health(mut reporter: HealthReporter, interval: Duration) {
+    let mut healthy = true;
+
+    loop {
+        let status = match timeout(interval, check_db_conn()).await {
+            Ok(Ok(())) => {
+                if !healthy {
+                    info!("DB connection restored");
+                }
+                ServingStatus::Serving
+            }
+
+            Ok(Err(e)) => {
+                if healthy {
+                    error!("DB connection check failed: {e}");
+                }
+                ServingStatus::NotServing
+            }
+
+            Err(_) => {
+                if healthy {
+                    error!("DB connection check timed out");
+                }
+                ServingStatus::NotServing
+            }
+        };
+
+        healthy = status == ServingStatus::Serving;
+        set_status(&mut reporter, status).await;
+        tokio::time::sleep(interval).await;
+    }
+}
@@ -1,17 +1,38 @@
-use crate::{db, jwt::JwtVerifier, middleware::AuthLayer, Context};
-use std::{fs::read_to_string, net::SocketAddr, sync::Arc};
+use crate::{
+    db, metrics,
+    middleware::{
+        prune_revocations_periodically, AuthLayer, MetricsLayer, RateLimitLayer, RateLimits,
+        RevocationLayer,
+    },
+    proto::FILE_DESCRIPTOR_SET,
+    ratings::refresh_categories_periodically,
+    Context,
+};
+use std::{fs::read_to_string, future::pending, net::SocketAddr, sync::Arc, time::Duration};
+use time::OffsetDateTime;
+use tokio::{
+    signal::{self, unix::SignalKind},
+    sync::Notify,
+    time::timeout,
+};
 use tonic::{
     transport::{Identity, Server, ServerTlsConfig},
     Status,
 };
-use tracing::warn;
+use tonic_health::ServingStatus;
+use tracing::{error, info, warn};
 
+mod admin;
 mod app;
 mod charts;
+mod health;
+mod publisher;
 mod user;
 
+use admin::AdminService;
 use app::RatingService;
 use charts::ChartService;
+use publisher::PublisherService;
 use user::UserService;
 
 impl From<db::Error> for Status {
@@ -20,9 +41,29 @@ impl From<db::Error> for Status {
     }
 }
 
+fn to_timestamp(t: OffsetDateTime) -> prost_types::Timestamp {
+    prost_types::Timestamp {
+        seconds: t.unix_timestamp(),
+        nanos: t.nanosecond() as i32,
+    }
+}
+
+/// Encodes the ID of the last row in a page as an opaque page token.
+fn encode_id_cursor(id: i32) -> String {
+    format!("{id:08x}")
+}
+
+/// Decodes a page token previously returned from [`encode_id_cursor`].
+fn decode_id_cursor(token: &str) -> Result<i32, Status> {
+    i32::from_str_radix(token, 16)
+        .ok()
+        .filter(|id| *id > 0)
+        .ok_or(Status::invalid_argument("invalid page token"))
+}
+
 pub async fn run_server(ctx: Context) -> Result<(), Box<dyn std::error::Error>> {
-    let verifier = JwtVerifier::from_secret(&ctx.config.jwt_secret)?;
     let addr: SocketAddr = ctx.config.socket().parse()?;
+    let metrics_addr: SocketAddr = ctx.config.metrics_socket().parse()?;
 
     let keychain_path = ctx.config.tls_keychain_path.clone();
     let key_path = ctx.config.tls_key_path.clone();
@@ -43,15 +84,109 @@ pub async fn run_server(ctx: Context) -> Result<(), Box<dyn std::error::Error>>
         }
     };
 
+    let (mut health_reporter, health_service) = tonic_health::server::health_reporter();
+    health::set_status(&mut health_reporter, ServingStatus::Serving).await;
+    let health_monitor = tokio::spawn(health::monitor_db_health(
+        health_reporter.clone(),
+        Duration::from_secs(ctx.config.health_check_interval_secs),
+    ));
+
+    let reflection_service = tonic_reflection::server::Builder::configure()
+        .register_encoded_file_descriptor_set(FILE_DESCRIPTOR_SET)
+        .register_encoded_file_descriptor_set(tonic_health::pb::FILE_DESCRIPTOR_SET)
+        .build_v1()?;
+
+    let drain_timeout = Duration::from_secs(ctx.config.shutdown_drain_timeout_secs);
     let ctx = Arc::new(ctx);
+    let category_refresher = tokio::spawn(refresh_categories_periodically(ctx.clone()));
+    let revocation_pruner = tokio::spawn(prune_revocations_periodically(Duration::from_secs(
+        [
+            ctx.config.jwt_access_token_lifetime_secs,
+            ctx.config.jwt_refresh_token_lifetime_secs,
+            ctx.config.jwt_admin_token_lifetime_secs,
+            ctx.config.jwt_publisher_token_lifetime_secs,
+        ]
+        .into_iter()
+        .max()
+        .unwrap_or_default(),
+    )));
+    let metrics_server = tokio::spawn(async move {
+        if let Err(e) = metrics::serve(metrics_addr).await {
+            error!("unable to serve metrics: {e}");
+        }
+    });
 
-    builder
-        .layer(AuthLayer::new(verifier))
+    let stop_accepting = Arc::new(Notify::new());
+    let server = builder
+        .layer(MetricsLayer)
+        .layer(AuthLayer::new(ctx.jwt_verifier.clone()))
+        .layer(RateLimitLayer::new(RateLimits::from_config(&ctx.config)))
+        .layer(RevocationLayer)
+        .add_service(AdminService::new_server(ctx.clone()))
         .add_service(RatingService::new_server(ctx.clone()))
         .add_service(ChartService::new_server(ctx.clone()))
+        .add_service(PublisherService::new_server(ctx.clone()))
         .add_service(UserService::new_server(ctx.clone()))
-        .serve(addr)
-        .await?;
+        .add_service(health_service)
+        .add_service(reflection_service)
+        .serve_with_shutdown(addr, {
+            let stop_accepting = stop_accepting.clone();
+            async move { stop_accepting.notified().await }
+        });
+    tokio::pin!(server);
+
+    tokio::select! {
+        res = &mut server => res?,
+
+        _ = shutdown_signal() => {
+            // Report that we are going away before we stop accepting new requests, then give
+            // in-flight requests a chance to complete.
+            info!("shutdown signal received, draining in-flight requests");
+            health_monitor.abort();
+            health::set_status(&mut health_reporter, ServingStatus::NotServing).await;
+            stop_accepting.notify_one();
+
+            match timeout(drain_timeout, &mut server).await {
+                Ok(res) => res?,
+                Err(_) => warn!(?drain_timeout, "timed out waiting for in-flight requests"),
+            }
+        }
+    }
+
+    // Background tasks would otherwise keep using the DB pool while it is being closed
+    for task in [category_refresher, revocation_pruner, metrics_server] {
+        task.abort();
+        let _ = task.await;
+    }
+
+    info!("server stopped");
 
     Ok(())
 }
+
+/// Resolves once the process receives either SIGINT or SIGTERM.
+async fn shutdown_signal() {
+    let sigint = async {
+        if let Err(e) = signal::ctrl_c().await {
+            error!("unable to listen for SIGINT: {e}");
+            pending::<()>().await;
+        }
+    };
+
+    let sigterm = async {
+        match signal::unix::signal(SignalKind::terminate()) {
+            Ok(mut s) => {
+                s.recv().await;
+            }
+            Err(e) => {
+                error!("unable to listen for SIGTERM: {e}");
+                pending::<()>().await;
+            }
+        }
+    };
+
+    tokio::select! {
+        _ = sigint => (),
+        _ = sigterm => (),
+    }
+}
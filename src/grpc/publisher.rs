@@ -0,0 +1,92 @@
+use crate::{
+    conn,
+    db::{self, Review},
+    jwt::Claims,
+    proto::publisher::{
+        publisher_server::{self, PublisherServer},
+        ReplyToReviewRequest,
+    },
+    ratings::get_snap_publisher,
+    Context,
+};
+use std::sync::Arc;
+use tonic::{Request, Response, Status};
+use tracing::{error, info};
+
+/// RPCs for snap publishers, only accessible with a publisher token (see [`AuthMiddleware`]).
+///
+/// [`AuthMiddleware`]: crate::middleware::AuthMiddleware
+#[derive(Clone)]
+pub struct PublisherService {
+    ctx: Arc<Context>,
+}
+
+impl PublisherService {
+    pub fn new_server(ctx: Arc<Context>) -> PublisherServer<PublisherService> {
+        PublisherServer::new(Self { ctx })
+    }
+}
+
+#[tonic::async_trait]
+impl publisher_server::Publisher for PublisherService {
+    async fn reply_to_review(
+        &self,
+        mut request: Request<ReplyToReviewRequest>,
+    ) -> Result<Response<()>, Status> {
+        let publisher_id = publisher_id(&mut request);
+        let ReplyToReviewRequest { review_id, body } = request.into_inner();
+
+        let body = body.trim();
+        if body.is_empty() {
+            return Err(Status::invalid_argument("reply body is empty"));
+        }
+        let max_length = self.ctx.config.review_max_length;
+        if body.chars().count() > max_length {
+            return Err(Status::invalid_argument(format!(
+                "reply body is longer than {max_length} characters"
+            )));
+        }
+
+        let review = match Review::get_by_id(review_id, conn!()).await {
+            Ok(Some(review)) => review,
+            Ok(None) => return Err(Status::not_found("review not found")),
+            Err(e) => {
+                error!("Error in get_review_by_id: {:?}", e);
+                return Err(Status::unknown("Internal server error"));
+            }
+        };
+
+        match get_snap_publisher(&review.snap_id, &self.ctx).await {
+            Ok(Some(owner)) if owner == publisher_id => (),
+            Ok(_) => return Err(Status::permission_denied("snap is not owned by publisher")),
+            Err(e) => {
+                error!(snap_id=%review.snap_id, "unable to fetch snap publisher: {e}");
+                return Err(Status::unavailable(
+                    "unable to verify snap ownership with snapcraft.io",
+                ));
+            }
+        }
+
+        match Review::reply(review_id, &publisher_id, body, conn!()).await {
+            Ok(()) => {
+                info!(%publisher_id, review_id, "publisher replied to review");
+                Ok(Response::new(()))
+            }
+
+            Err(db::Error::ReviewNotFound) => Err(Status::not_found("review not found")),
+
+            Err(e) => {
+                error!("Error in reply_to_review: {:?}", e);
+                Err(Status::unknown("Internal server error"))
+            }
+        }
+    }
+}
+
+fn publisher_id<T>(request: &mut Request<T>) -> String {
+    request
+        .extensions_mut()
+        .remove::<Claims>()
+        .and_then(|claims| claims.publisher_id)
+        .expect("expected request to have publisher claims")
+}
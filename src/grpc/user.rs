@@ -1,16 +1,22 @@
 use crate::{
     conn,
-    db::{User, Vote},
-    jwt::Claims,
+    db::{
+        self, flag_vote_bursts, BannedClient, ContentReport, ReportReason, Review, User, Vote,
+        VoteReason,
+    },
+    jwt::{Claims, TokenType},
+    metrics::metrics,
     proto::user::{
         user_server::{self, UserServer},
-        AuthenticateRequest, AuthenticateResponse, GetSnapVotesRequest, GetSnapVotesResponse,
+        AuthenticateRequest, AuthenticateResponse, DeleteReviewRequest, DeleteReviewResponse,
+        GetSnapVotesRequest, GetSnapVotesResponse, RefreshTokenRequest, RefreshTokenResponse,
+        ReportContentRequest, RetractVoteRequest, RetractVoteResponse, SubmitReviewRequest,
         Vote as PbVote, VoteRequest,
     },
-    ratings::{get_snap_name, update_categories, Error},
+    ratings::{get_snap_names, update_categories},
     Context,
 };
-use futures::future::try_join_all;
+use sqlx::PgConnection;
 use std::sync::Arc;
 use time::OffsetDateTime;
 use tonic::{Request, Response, Status};
@@ -31,6 +37,20 @@ impl UserService {
     }
 }
 
+impl UserService {
+    /// Issue a new access token and refresh token for the given client
+    fn token_pair(&self, client_hash: String) -> Result<(String, String), Status> {
+        let encoder = &self.ctx.jwt_encoder;
+        match (
+            encoder.encode(client_hash.clone()),
+            encoder.encode_refresh(client_hash),
+        ) {
+            (Ok(token), Ok(refresh_token)) => Ok((token, refresh_token)),
+            _ => Err(Status::internal("internal error")),
+        }
+    }
+}
+
 #[tonic::async_trait]
 impl user_server::User for UserService {
     async fn authenticate(
@@ -46,16 +66,64 @@ impl user_server::User for UserService {
             return Err(Status::invalid_argument(error));
         }
 
-        match User::create_or_seen(&id, conn!()).await {
-            Ok(user) => match self.ctx.jwt_encoder.encode(user.client_hash) {
-                Ok(token) => Ok(Response::new(AuthenticateResponse { token })),
-                Err(_) => Err(Status::internal("internal error")),
-            },
+        let conn = conn!();
+        reject_banned(&id, conn).await?;
+
+        match User::create_or_seen(&id, conn).await {
+            Ok(user) => {
+                let (token, refresh_token) = self.token_pair(user.client_hash)?;
+                Ok(Response::new(AuthenticateResponse {
+                    token,
+                    refresh_token,
+                }))
+            }
 
             Err(_error) => Err(Status::invalid_argument("id")),
         }
     }
 
+    async fn refresh_token(
+        &self,
+        request: Request<RefreshTokenRequest>,
+    ) -> Result<Response<RefreshTokenResponse>, Status> {
+        let RefreshTokenRequest { refresh_token } = request.into_inner();
+
+        let claims = match self.ctx.jwt_verifier.decode(&refresh_token) {
+            Ok(claims) if claims.typ == TokenType::Refresh => claims,
+            _ => return Err(Status::unauthenticated("invalid refresh token")),
+        };
+
+        let conn = conn!();
+        reject_banned(&claims.sub, conn).await?;
+
+        let expires_at = OffsetDateTime::from_unix_timestamp(claims.exp as i64)
+            .map_err(|_| Status::unauthenticated("invalid refresh token"))?;
+
+        // Each refresh token can only be exchanged once so that a leaked token stops working as
+        // soon as either party has used it
+        let result = match User::mark_seen(&claims.sub, conn).await {
+            Ok(true) => User::use_refresh_token(&claims.sub, &claims.jti, expires_at, conn).await,
+            res => res,
+        };
+
+        match result {
+            Ok(true) => {
+                let (token, refresh_token) = self.token_pair(claims.sub)?;
+                Ok(Response::new(RefreshTokenResponse {
+                    token,
+                    refresh_token,
+                }))
+            }
+
+            Ok(false) => Err(Status::unauthenticated("invalid refresh token")),
+
+            Err(e) => {
+                error!("Error in refresh_token: {:?}", e);
+                Err(Status::unknown("Internal server error"))
+            }
+        }
+    }
+
     async fn delete(&self, mut request: Request<()>) -> Result<Response<()>, Status> {
         let Claims {
             sub: client_hash, ..
@@ -77,27 +145,77 @@ impl user_server::User for UserService {
             snap_id,
             snap_revision,
             vote_up,
+            reasons,
         } = request.into_inner();
-        let conn = conn!();
+
+        if vote_up && !reasons.is_empty() {
+            return Err(Status::invalid_argument(
+                "reasons can only be given for negative votes",
+            ));
+        }
+        let mut reasons = reasons
+            .into_iter()
+            .map(VoteReason::from_repr)
+            .collect::<Option<Vec<_>>>()
+            .ok_or(Status::invalid_argument("invalid vote reason"))?;
+        reasons.sort_unstable();
+        reasons.dedup();
 
         // Ignore but log warning, it's not fatal
-        if let Err(e) = update_categories(&snap_id, &self.ctx, conn).await {
+        if let Err(e) = update_categories(&snap_id, &self.ctx).await {
             warn!("unable to update categories for snap: {e}");
         }
 
+        let conn = conn!();
         let vote = Vote {
             client_hash: sub,
-            snap_id,
+            snap_id: snap_id.clone(),
             snap_revision: snap_revision as u32,
             vote_up,
             timestamp: OffsetDateTime::now_utc(),
+            reasons,
         };
 
-        match vote.save_to_db(conn).await {
-            Ok(_) => Ok(Response::new(())),
+        if let Err(e) = vote.save_to_db(conn).await {
+            return match e {
+                db::Error::UserNotFound => Err(Status::not_found("user not found")),
+                e => {
+                    error!("Error in save_vote_to_db: {:?}", e);
+                    Err(Status::unknown("Internal server error"))
+                }
+            };
+        }
+
+        // The vote is recorded either way, flagging only affects how it is counted
+        match flag_vote_bursts(&snap_id, self.ctx.burst_detection, conn).await {
+            Ok(0) => (),
+            Ok(n) => {
+                warn!(%snap_id, n_flagged = n, "flagged burst of votes from new users");
+                metrics().flagged_votes.inc_by(n);
+            }
+            Err(e) => warn!(%snap_id, "unable to check for vote bursts: {e}"),
+        }
+
+        Ok(Response::new(()))
+    }
+
+    async fn retract_vote(
+        &self,
+        mut request: Request<RetractVoteRequest>,
+    ) -> Result<Response<RetractVoteResponse>, Status> {
+        let Claims {
+            sub: client_hash, ..
+        } = claims(&mut request);
+        let RetractVoteRequest {
+            snap_id,
+            snap_revision,
+        } = request.into_inner();
+
+        match Vote::delete(&client_hash, &snap_id, snap_revision as u32, conn!()).await {
+            Ok(retracted) => Ok(Response::new(RetractVoteResponse { retracted })),
 
             Err(e) => {
-                error!("Error in save_vote_to_db: {:?}", e);
+                error!("Error in retract_vote: {:?}", e);
                 Err(Status::unknown("Internal server error"))
             }
         }
@@ -112,27 +230,28 @@ impl user_server::User for UserService {
         } = claims(&mut request);
         let GetSnapVotesRequest { snap_id } = request.into_inner();
 
-        let conn = conn!();
-
         // Ignore but log warning, it's not fatal
-        if let Err(e) = update_categories(&snap_id, &self.ctx, conn).await {
+        if let Err(e) = update_categories(&snap_id, &self.ctx).await {
             warn!("unable to update categories for snap: {e}");
         }
 
-        match Vote::get_all_by_client_hash(&client_hash, Some(snap_id), conn).await {
+        // Bound separately so that the connection is released before looking up snap names
+        let votes = Vote::get_all_by_client_hash(&client_hash, Some(snap_id), conn!()).await;
+        match votes {
             Ok(votes) => {
-                let votes = try_join_all(votes.into_iter().map(|vote| async {
-                    let snap_name = get_snap_name(
-                        &vote.snap_id,
-                        &self.ctx.config.snapcraft_io_uri,
-                        &self.ctx.http_client,
-                    )
-                    .await?;
-
-                    Result::<PbVote, Error>::Ok(PbVote::from_vote_and_snap_name(vote, &snap_name))
-                }))
-                .await
-                .map_err(|_| Status::unknown("Internal server error"))?;
+                let snap_ids: Vec<String> = votes.iter().map(|v| v.snap_id.clone()).collect();
+                let snap_names = get_snap_names(&snap_ids, &self.ctx).await.map_err(|e| {
+                    error!("unable to fetch snap names: {e}");
+                    Status::unknown("Internal server error")
+                })?;
+
+                let votes = votes
+                    .into_iter()
+                    .map(|vote| {
+                        let snap_name = snap_names.get(&vote.snap_id).cloned().unwrap_or_default();
+                        PbVote::from_vote_and_snap_name(vote, &snap_name)
+                    })
+                    .collect();
                 let payload = GetSnapVotesResponse { votes };
 
                 Ok(Response::new(payload))
@@ -144,6 +263,105 @@ impl user_server::User for UserService {
             }
         }
     }
+
+    async fn submit_review(
+        &self,
+        mut request: Request<SubmitReviewRequest>,
+    ) -> Result<Response<()>, Status> {
+        let Claims {
+            sub: client_hash, ..
+        } = claims(&mut request);
+        let SubmitReviewRequest {
+            snap_id,
+            snap_revision,
+            body,
+        } = request.into_inner();
+
+        if snap_id.is_empty() {
+            return Err(Status::invalid_argument("snap id"));
+        }
+        if snap_revision <= 0 {
+            return Err(Status::invalid_argument("snap revision"));
+        }
+        let body = body.trim();
+        if body.is_empty() {
+            return Err(Status::invalid_argument("review body is empty"));
+        }
+        let max_length = self.ctx.config.review_max_length;
+        if body.chars().count() > max_length {
+            return Err(Status::invalid_argument(format!(
+                "review body is longer than {max_length} characters"
+            )));
+        }
+
+        match Review::save(&client_hash, &snap_id, snap_revision as u32, body, conn!()).await {
+            Ok(()) => Ok(Response::new(())),
+
+            Err(db::Error::UserNotFound) => Err(Status::not_found("user not found")),
+
+            Err(db::Error::ReviewRemoved) => Err(Status::permission_denied(
+                "your review of this snap was removed by a moderator",
+            )),
+
+            Err(e) => {
+                error!("Error in submit_review: {:?}", e);
+                Err(Status::unknown("Internal server error"))
+            }
+        }
+    }
+
+    async fn delete_review(
+        &self,
+        mut request: Request<DeleteReviewRequest>,
+    ) -> Result<Response<DeleteReviewResponse>, Status> {
+        let Claims {
+            sub: client_hash, ..
+        } = claims(&mut request);
+        let DeleteReviewRequest { snap_id } = request.into_inner();
+
+        match Review::delete(&client_hash, &snap_id, conn!()).await {
+            Ok(deleted) => Ok(Response::new(DeleteReviewResponse { deleted })),
+
+            Err(e) => {
+                error!("Error in delete_review: {:?}", e);
+                Err(Status::unknown("Internal server error"))
+            }
+        }
+    }
+
+    async fn report_content(
+        &self,
+        mut request: Request<ReportContentRequest>,
+    ) -> Result<Response<()>, Status> {
+        let Claims {
+            sub: client_hash, ..
+        } = claims(&mut request);
+        let ReportContentRequest {
+            review_id,
+            reason,
+            details,
+        } = request.into_inner();
+
+        let reason = ReportReason::from_repr(reason).ok_or(Status::invalid_argument("reason"))?;
+        let details = details.trim();
+        let max_length = self.ctx.config.review_max_length;
+        if details.chars().count() > max_length {
+            return Err(Status::invalid_argument(format!(
+                "report details are longer than {max_length} characters"
+            )));
+        }
+
+        match ContentReport::create(review_id, &client_hash, reason, details, conn!()).await {
+            Ok(()) => Ok(Response::new(())),
+
+            Err(db::Error::ReviewNotFound) => Err(Status::not_found("review not found")),
+
+            Err(e) => {
+                error!("Error in report_content: {:?}", e);
+                Err(Status::unknown("Internal server error"))
+            }
+        }
+    }
 }
 
 impl PbVote {
@@ -159,6 +377,19 @@ impl PbVote {
             vote_up: value.vote_up,
             timestamp,
             snap_name: snap_name.into(),
+            reasons: value.reasons.into_iter().map(|r| r as i32).collect(),
+        }
+    }
+}
+
+/// Banned clients are unable to obtain new tokens.
+async fn reject_banned(client_hash: &str, conn: &mut PgConnection) -> Result<(), Status> {
+    match BannedClient::get_by_client_hash(client_hash, conn).await {
+        Ok(None) => Ok(()),
+        Ok(Some(_)) => Err(Status::permission_denied("client is banned")),
+        Err(e) => {
+            error!("Error in get_banned_client_by_client_hash: {:?}", e);
+            Err(Status::unknown("Internal server error"))
         }
     }
 }
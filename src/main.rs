@@ -1,10 +1,26 @@
-use ratings::{db::check_db_conn, grpc::run_server, Config, Context};
-use std::io::stdout;
+use ratings::{
+    db::{check_db_conn, close_pool},
+    grpc::run_server,
+    jwt::{JwtEncoder, Keyring},
+    Config, Context,
+};
+use std::{env, io::stdout, time::Duration};
 use tracing::{info, subscriber::set_global_default};
 use tracing_subscriber::{EnvFilter, FmtSubscriber};
 
+const USAGE: &str =
+    "usage: ratings [mint-admin-token <name> | mint-publisher-token <publisher-id>]";
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.as_slice() {
+        [] => (),
+        [cmd, name] if cmd == "mint-admin-token" => return mint_admin_token(name),
+        [cmd, id] if cmd == "mint-publisher-token" => return mint_publisher_token(id),
+        _ => return Err(USAGE.into()),
+    }
+
     let subscriber = FmtSubscriber::builder()
         .with_env_filter(EnvFilter::from_default_env())
         .with_writer(stdout)
@@ -26,7 +42,31 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     check_db_conn().await?; // Ensure that the migrations run before server start
 
     info!("starting server");
+    let close_timeout = Duration::from_secs(ctx.config.shutdown_drain_timeout_secs);
     run_server(ctx).await?;
+    close_pool(close_timeout).await;
+
+    Ok(())
+}
+
+/// Print a new admin token for `name`, signed with the configured signing key.
+fn mint_admin_token(name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let config = Config::load()?;
+    let keyring = Keyring::from_config(&config)?;
+    let token = JwtEncoder::from_config(&config, &keyring).encode_admin(name.to_string())?;
+    println!("{token}");
+
+    Ok(())
+}
+
+/// Print a new publisher token for the snapcraft.io account with the given publisher ID, signed
+/// with the configured signing key.
+fn mint_publisher_token(publisher_id: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let config = Config::load()?;
+    let keyring = Keyring::from_config(&config)?;
+    let token =
+        JwtEncoder::from_config(&config, &keyring).encode_publisher(publisher_id.to_string())?;
+    println!("{token}");
 
     Ok(())
 }
@@ -0,0 +1,270 @@
+//! Prometheus metrics for the service, served over HTTP at `/metrics`.
+//!
+//! Most metrics are recorded as events happen, the exception being DB pool usage and cache
+//! statistics which are sampled each time the metrics are scraped.
+use crate::{cache, db, proto::FILE_DESCRIPTOR_SET};
+use axum::{http::header::CONTENT_TYPE, routing::get, Router};
+use prometheus::{
+    exponential_buckets, Encoder, HistogramOpts, HistogramVec, IntCounter, IntCounterVec,
+    IntGaugeVec, Opts, Registry, TextEncoder,
+};
+use prost::Message;
+use prost_types::FileDescriptorSet;
+use std::{collections::HashSet, net::SocketAddr, sync::OnceLock};
+use tracing::{error, info};
+
+/// The `method` label used for requests to paths that aren't one of the RPCs we serve
+pub const UNKNOWN_METHOD: &str = "unknown";
+
+static METRICS: OnceLock<Metrics> = OnceLock::new();
+static KNOWN_METHODS: OnceLock<HashSet<String>> = OnceLock::new();
+
+/// The collectors for all metrics exported by the service.
+pub struct Metrics {
+    registry: Registry,
+    /// gRPC requests by method and status code
+    pub grpc_requests: IntCounterVec,
+    /// gRPC request latency by method
+    pub grpc_request_duration: HistogramVec,
+    /// gRPC requests rejected by rate limiting, by method
+    pub rate_limited_requests: IntCounterVec,
+    /// Votes flagged as suspicious
+    pub flagged_votes: IntCounter,
+    /// Requests made to snapcraft.io, by outcome
+    pub snapcraft_requests: IntCounterVec,
+    /// snapcraft.io request latency, by outcome
+    pub snapcraft_request_duration: HistogramVec,
+    db_pool_connections: IntGaugeVec,
+    cache_hits: IntCounterVec,
+    cache_misses: IntCounterVec,
+    cache_entries: IntGaugeVec,
+}
+
+/// The global [`Metrics`], created and registered on first use.
+pub fn metrics() -> &'static Metrics {
+    METRICS.get_or_init(|| Metrics::new().expect("metric definitions to be valid"))
+}
+
+impl Metrics {
+    fn new() -> Result<Self, prometheus::Error> {
+        let registry = Registry::new_custom(Some("ratings".to_string()), None)?;
+
+        let grpc_requests = IntCounterVec::new(
+            Opts::new("grpc_requests_total", "gRPC requests handled"),
+            &["method", "code"],
+        )?;
+        let grpc_request_duration = HistogramVec::new(
+            HistogramOpts::new("grpc_request_duration_seconds", "gRPC request latency")
+                .buckets(exponential_buckets(0.001, 2.0, 14)?),
+            &["method"],
+        )?;
+        let rate_limited_requests = IntCounterVec::new(
+            Opts::new(
+                "rate_limited_requests_total",
+                "gRPC requests rejected for exceeding a rate limit",
+            ),
+            &["method"],
+        )?;
+        let flagged_votes = IntCounter::new(
+            "flagged_votes_total",
+            "Votes flagged as part of a burst from new users",
+        )?;
+        let snapcraft_requests = IntCounterVec::new(
+            Opts::new("snapcraft_requests_total", "Requests made to snapcraft.io"),
+            &["outcome"],
+        )?;
+        let snapcraft_request_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "snapcraft_request_duration_seconds",
+                "snapcraft.io request latency",
+            )
+            .buckets(exponential_buckets(0.01, 2.0, 12)?),
+            &["outcome"],
+        )?;
+        let db_pool_connections = IntGaugeVec::new(
+            Opts::new("db_pool_connections", "DB connection pool usage"),
+            &["state"],
+        )?;
+        let cache_hits = IntCounterVec::new(
+            Opts::new("cache_hits_total", "Cache lookups that found an entry"),
+            &["cache"],
+        )?;
+        let cache_misses = IntCounterVec::new(
+            Opts::new("cache_misses_total", "Cache lookups that found no entry"),
+            &["cache"],
+        )?;
+        let cache_entries = IntGaugeVec::new(
+            Opts::new("cache_entries", "Entries currently cached"),
+            &["cache"],
+        )?;
+
+        registry.register(Box::new(grpc_requests.clone()))?;
+        registry.register(Box::new(grpc_request_duration.clone()))?;
+        registry.register(Box::new(rate_limited_requests.clone()))?;
+        registry.register(Box::new(flagged_votes.clone()))?;
+        registry.register(Box::new(snapcraft_requests.clone()))?;
+        registry.register(Box::new(snapcraft_request_duration.clone()))?;
+        registry.register(Box::new(db_pool_connections.clone()))?;
+        registry.register(Box::new(cache_hits.clone()))?;
+        registry.register(Box::new(cache_misses.clone()))?;
+        registry.register(Box::new(cache_entries.clone()))?;
+
+        Ok(Self {
+            registry,
+            grpc_requests,
+            grpc_request_duration,
+            rate_limited_requests,
+            flagged_votes,
+            snapcraft_requests,
+            snapcraft_request_duration,
+            db_pool_connections,
+            cache_hits,
+            cache_misses,
+            cache_entries,
+        })
+    }
+
+    /// Sample the DB pool and cache statistics and render all metrics in the prometheus text
+    /// exposition format.
+    pub async fn render(&self) -> String {
+        match db::get_pool().await {
+            Ok(pool) => {
+                let idle = pool.num_idle() as i64;
+                let size = pool.size() as i64;
+                let max = pool.options().get_max_connections() as i64;
+                self.db_pool_connections
+                    .with_label_values(&["idle"])
+                    .set(idle);
+                self.db_pool_connections
+                    .with_label_values(&["in_use"])
+                    .set(size - idle);
+                self.db_pool_connections
+                    .with_label_values(&["max"])
+                    .set(max);
+            }
+            Err(e) => error!("unable to sample DB pool metrics: {e}"),
+        }
+
+        for (name, stats) in [
+            ("vote_summaries", cache::vote_summaries().stats()),
+            ("charts", cache::charts().stats()),
+            ("token_revocations", cache::token_revocations().stats()),
+        ] {
+            sync_counter(&self.cache_hits.with_label_values(&[name]), stats.hits);
+            sync_counter(&self.cache_misses.with_label_values(&[name]), stats.misses);
+            self.cache_entries
+                .with_label_values(&[name])
+                .set(stats.entries as i64);
+        }
+
+        let mut buf = Vec::new();
+        if let Err(e) = TextEncoder::new().encode(&self.registry.gather(), &mut buf) {
+            error!("unable to encode metrics: {e}");
+        }
+
+        String::from_utf8(buf).unwrap_or_default()
+    }
+}
+
+/// The `method` label to record a request to `path` under.
+///
+/// Requests are counted before they are authenticated, so only paths naming one of the RPCs we
+/// serve are used as labels with everything else bucketed as [`UNKNOWN_METHOD`]. Otherwise any
+/// client could create an unbounded number of series by requesting made up paths.
+pub fn method_label(path: &str) -> &'static str {
+    known_methods()
+        .get(path.trim_start_matches('/'))
+        .map_or(UNKNOWN_METHOD, String::as_str)
+}
+
+/// The full names (`package.Service/Method`) of every RPC we serve, read from the descriptors
+/// of our own services along with the health and reflection services.
+fn known_methods() -> &'static HashSet<String> {
+    KNOWN_METHODS.get_or_init(|| {
+        [
+            FILE_DESCRIPTOR_SET,
+            tonic_health::pb::FILE_DESCRIPTOR_SET,
+            tonic_reflection::pb::v1::FILE_DESCRIPTOR_SET,
+        ]
+        .into_iter()
+        .flat_map(|bytes| {
+            FileDescriptorSet::decode(bytes)
+                .expect("file descriptor sets to be valid")
+                .file
+        })
+        .flat_map(|file| {
+            let package = file.package().to_string();
+            file.service.into_iter().flat_map(move |service| {
+                let prefix = format!("{package}.{}", service.name());
+                service
+                    .method
+                    .into_iter()
+                    .map(move |method| format!("{prefix}/{}", method.name()))
+            })
+        })
+        .collect()
+    })
+}
+
+/// Bring a prometheus counter up to date with a counter tracked elsewhere.
+fn sync_counter(counter: &IntCounter, value: u64) {
+    counter.inc_by(value.saturating_sub(counter.get()));
+}
+
+/// Serve the `/metrics` endpoint on the given address until the process exits.
+pub async fn serve(addr: SocketAddr) -> std::io::Result<()> {
+    let app = Router::new().route(
+        "/metrics",
+        get(|| async {
+            (
+                [(CONTENT_TYPE, "text/plain; version=0.0.4")],
+                metrics().render().await,
+            )
+        }),
+    );
+
+    info!(%addr, "serving metrics");
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    axum::serve(listener, app).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counters_are_synced_to_external_values() {
+        let counter = IntCounter::new("test", "test").unwrap();
+
+        sync_counter(&counter, 3);
+        assert_eq!(counter.get(), 3);
+        sync_counter(&counter, 5);
+        assert_eq!(counter.get(), 5);
+        sync_counter(&counter, 5);
+        assert_eq!(counter.get(), 5);
+    }
+
+    #[test]
+    fn known_methods_are_used_as_labels() {
+        for path in [
+            "/ratings.features.app.App/GetRating",
+            "/ratings.features.user.User/Authenticate",
+            "/grpc.health.v1.Health/Check",
+            "/grpc.reflection.v1.ServerReflection/ServerReflectionInfo",
+        ] {
+            assert_eq!(method_label(path), &path[1..]);
+        }
+    }
+
+    #[test]
+    fn unknown_paths_share_a_label() {
+        for path in [
+            "/ratings.features.app.App/NotARealMethod",
+            "/made.up.Service/GetRating",
+            "/",
+            "",
+        ] {
+            assert_eq!(method_label(path), UNKNOWN_METHOD);
+        }
+    }
+}
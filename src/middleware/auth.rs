@@ -1,23 +1,36 @@
 //! A custom Tower [Layer] for validating jwt tokens and attaching the decoded claim to incoming
 //! requests.
-use crate::jwt::JwtVerifier;
+//!
+//! Only the token itself is checked here, revoked tokens are rejected further down the stack by
+//! [`RevocationLayer`](super::RevocationLayer) once the request has passed rate limiting.
+use crate::{
+    jwt::{JwtVerifier, Role, TokenType},
+    middleware::{BoxError, BoxFuture},
+};
 use http::{Request, Response};
 use std::{
-    error::Error,
-    future::Future,
     mem::replace,
-    pin::Pin,
     sync::Arc,
     task::{Context, Poll},
 };
 use tonic::Status;
 use tower::{Layer, Service};
 
-type BoxFuture<T> = Pin<Box<dyn Future<Output = T> + Send + 'static>>;
-type BoxError = Box<dyn Error + Send + Sync>;
-
 /// The paths which are accessible without authentication
-pub const PUBLIC_PATHS: [&str; 1] = ["ratings.features.user.User/Authenticate"];
+pub const PUBLIC_PATHS: [&str; 6] = [
+    "ratings.features.user.User/Authenticate",
+    "ratings.features.user.User/RefreshToken",
+    "ratings.features.app.App/ListReviews",
+    "grpc.health.v1.Health/Check",
+    "grpc.health.v1.Health/Watch",
+    "grpc.reflection.v1.ServerReflection/ServerReflectionInfo",
+];
+
+/// The prefix of paths which may only be called with an admin token
+pub const ADMIN_PATH_PREFIX: &str = "/ratings.features.admin.Admin/";
+
+/// The prefix of paths which may only be called with a publisher token
+pub const PUBLISHER_PATH_PREFIX: &str = "/ratings.features.publisher.Publisher/";
 
 #[derive(Clone)]
 pub struct AuthLayer {
@@ -90,12 +103,27 @@ where
                 return unauthenticated!("malformed auth header");
             }
 
-            match self.verifier.decode(parts[1]) {
-                Ok(claims) => {
-                    req.extensions_mut().insert(claims);
-                }
+            let claims = match self.verifier.decode(parts[1]) {
+                Ok(claims) if claims.typ == TokenType::Access => claims,
+                Ok(_) => return unauthenticated!("refresh tokens can not be used for access"),
                 Err(_) => return unauthenticated!("invalid auth header"),
+            };
+
+            if req.uri().path().starts_with(ADMIN_PATH_PREFIX) && claims.role != Role::Admin {
+                return Box::pin(async move {
+                    Err(Box::new(Status::permission_denied("admin role required")) as BoxError)
+                });
+            }
+
+            if req.uri().path().starts_with(PUBLISHER_PATH_PREFIX)
+                && (claims.role != Role::Publisher || claims.publisher_id.is_none())
+            {
+                return Box::pin(async move {
+                    Err(Box::new(Status::permission_denied("publisher role required")) as BoxError)
+                });
             }
+
+            req.extensions_mut().insert(claims);
         }
 
         Box::pin(async move { inner.call(req).await })
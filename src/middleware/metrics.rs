@@ -0,0 +1,103 @@
+//! A custom Tower [Layer] for recording request counts, latencies and status codes for each RPC.
+use crate::{
+    metrics::{method_label, metrics},
+    middleware::{BoxError, BoxFuture},
+};
+use http::{HeaderMap, Request, Response};
+use std::{
+    mem::replace,
+    task::{Context, Poll},
+    time::Instant,
+};
+use tonic::{Code, Status};
+use tower::{Layer, Service};
+
+#[derive(Clone, Default)]
+pub struct MetricsLayer;
+
+impl<S> Layer<S> for MetricsLayer {
+    type Service = MetricsMiddleware<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        MetricsMiddleware { inner }
+    }
+}
+
+#[derive(Clone)]
+pub struct MetricsMiddleware<S> {
+    inner: S,
+}
+
+impl<S, T, U> Service<Request<T>> for MetricsMiddleware<S>
+where
+    S: Service<Request<T>, Response = Response<U>, Error = BoxError> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    T: Send + 'static,
+{
+    type Response = Response<U>;
+    type Error = BoxError;
+    type Future = BoxFuture<Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<T>) -> Self::Future {
+        // See: https://docs.rs/tower/latest/tower/trait.Service.html#be-careful-when-cloning-inner-services
+        let clone = self.inner.clone();
+        let mut inner = replace(&mut self.inner, clone);
+        let method = method_label(req.uri().path());
+
+        Box::pin(async move {
+            let start = Instant::now();
+            let res = inner.call(req).await;
+
+            let code = match &res {
+                Ok(resp) => status_from_headers(resp.headers()),
+                Err(e) => e
+                    .downcast_ref::<Status>()
+                    .map_or(Code::Unknown, Status::code),
+            };
+
+            let m = metrics();
+            m.grpc_request_duration
+                .with_label_values(&[method])
+                .observe(start.elapsed().as_secs_f64());
+            m.grpc_requests
+                .with_label_values(&[method, &format!("{code:?}")])
+                .inc();
+
+            res
+        })
+    }
+}
+
+/// Errors are returned as "trailers only" responses with the status in the headers, successful
+/// responses carry their status in the trailers so we treat a missing status as OK.
+fn status_from_headers(headers: &HeaderMap) -> Code {
+    headers
+        .get("grpc-status")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|s| s.parse::<i32>().ok())
+        .map_or(Code::Ok, Code::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use simple_test_case::test_case;
+
+    #[test_case(None, Code::Ok; "missing")]
+    #[test_case(Some("0"), Code::Ok; "ok")]
+    #[test_case(Some("5"), Code::NotFound; "not found")]
+    #[test_case(Some("16"), Code::Unauthenticated; "unauthenticated")]
+    #[test]
+    fn status_is_read_from_headers(status: Option<&str>, expected: Code) {
+        let mut headers = HeaderMap::new();
+        if let Some(s) = status {
+            headers.insert("grpc-status", s.parse().unwrap());
+        }
+
+        assert_eq!(status_from_headers(&headers), expected);
+    }
+}
@@ -0,0 +1,17 @@
+//! Custom Tower [Layer]s applied to all incoming requests.
+//!
+//! [Layer]: tower::Layer
+use std::{error::Error, future::Future, pin::Pin};
+
+mod auth;
+mod metrics;
+mod rate_limit;
+mod revocation;
+
+pub use auth::{AuthLayer, AuthMiddleware, ADMIN_PATH_PREFIX, PUBLIC_PATHS, PUBLISHER_PATH_PREFIX};
+pub use metrics::{MetricsLayer, MetricsMiddleware};
+pub use rate_limit::{RateLimitLayer, RateLimitMiddleware, RateLimits};
+pub use revocation::{prune_revocations_periodically, RevocationLayer, RevocationMiddleware};
+
+type BoxFuture<T> = Pin<Box<dyn Future<Output = T> + Send + 'static>>;
+type BoxError = Box<dyn Error + Send + Sync>;
@@ -0,0 +1,464 @@
+//! A custom Tower [Layer] for limiting the rate at which each client can make requests.
+//!
+//! Authenticated requests are limited per client hash (the `sub` of their token) while requests to
+//! [`PUBLIC_PATHS`] are limited per peer IP address. Each client gets a token bucket per quota
+//! which holds up to a minute's worth of requests and refills continuously, so short bursts are
+//! allowed as long as the average rate stays within the quota.
+//!
+//! This layer runs after JWT validation but before anything that needs the DB, so that clients
+//! over their quota are turned away as cheaply as possible.
+use crate::{
+    jwt::Claims,
+    metrics::{method_label, metrics},
+    middleware::{BoxError, BoxFuture, PUBLIC_PATHS},
+    Config,
+};
+use http::{Request, Response};
+use std::{
+    collections::HashMap,
+    mem::replace,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+use tonic::{
+    metadata::MetadataValue,
+    transport::server::{TcpConnectInfo, TlsConnectInfo},
+    Status,
+};
+use tower::{Layer, Service};
+
+/// The number of buckets we track before evicting those that have refilled or gone unused
+const MAX_TRACKED_BUCKETS: usize = 100_000;
+
+/// The RPCs that are limited by the authentication quota
+const AUTHENTICATE_PATHS: [&str; 2] = [
+    "ratings.features.user.User/Authenticate",
+    "ratings.features.user.User/RefreshToken",
+];
+
+/// The RPCs that are limited by the voting quota
+const VOTE_PATHS: [&str; 2] = [
+    "ratings.features.user.User/Vote",
+    "ratings.features.user.User/RetractVote",
+];
+
+/// The number of requests per minute each client may make, by RPC. A quota of zero disables
+/// limiting for the RPCs it covers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimits {
+    /// `User/Authenticate` and `User/RefreshToken`, per peer IP
+    pub authenticate_per_minute: u32,
+    /// `User/Vote` and `User/RetractVote`, per client
+    pub vote_per_minute: u32,
+    /// All other RPCs, per client or per peer IP if unauthenticated
+    pub default_per_minute: u32,
+}
+
+impl Default for RateLimits {
+    fn default() -> Self {
+        Self {
+            authenticate_per_minute: 30,
+            vote_per_minute: 60,
+            default_per_minute: 600,
+        }
+    }
+}
+
+impl RateLimits {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            authenticate_per_minute: config.rate_limit_authenticate_per_minute,
+            vote_per_minute: config.rate_limit_vote_per_minute,
+            default_per_minute: config.rate_limit_default_per_minute,
+        }
+    }
+
+    fn per_minute(&self, quota: Quota) -> u32 {
+        match quota {
+            Quota::Authenticate => self.authenticate_per_minute,
+            Quota::Vote => self.vote_per_minute,
+            Quota::Default => self.default_per_minute,
+        }
+    }
+}
+
+/// The quotas that requests are counted against
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Quota {
+    Authenticate,
+    Vote,
+    Default,
+}
+
+impl Quota {
+    fn for_path(path: &str) -> Self {
+        if AUTHENTICATE_PATHS.iter().any(|p| path.ends_with(p)) {
+            Self::Authenticate
+        } else if VOTE_PATHS.iter().any(|p| path.ends_with(p)) {
+            Self::Vote
+        } else {
+            Self::Default
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    /// Top up the bucket for the time elapsed since it was last updated.
+    fn refill(&mut self, per_minute: u32, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        let capacity = per_minute as f64;
+        self.tokens = (self.tokens + elapsed * capacity / 60.0).min(capacity);
+        self.updated = now;
+    }
+}
+
+/// Token buckets for each client and quota.
+#[derive(Debug)]
+struct RateLimiter {
+    limits: RateLimits,
+    max_buckets: usize,
+    buckets: Mutex<HashMap<(Quota, String), Bucket>>,
+}
+
+impl RateLimiter {
+    fn new(limits: RateLimits) -> Self {
+        Self {
+            limits,
+            max_buckets: MAX_TRACKED_BUCKETS,
+            buckets: Default::default(),
+        }
+    }
+
+    /// Take a token from the client's bucket for the given path, returning how long the client
+    /// needs to wait before retrying if the bucket is empty.
+    fn check(&self, path: &str, key: &str, now: Instant) -> Result<(), Duration> {
+        let quota = Quota::for_path(path);
+        let per_minute = self.limits.per_minute(quota);
+        if per_minute == 0 {
+            return Ok(());
+        }
+
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() >= self.max_buckets {
+            self.evict(&mut buckets, now);
+        }
+
+        let bucket = buckets.entry((quota, key.to_string())).or_insert(Bucket {
+            tokens: per_minute as f64,
+            updated: now,
+        });
+        bucket.refill(per_minute, now);
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            let wait = (1.0 - bucket.tokens) * 60.0 / per_minute as f64;
+            Err(Duration::from_secs_f64(wait))
+        }
+    }
+
+    /// Bring the number of tracked buckets down to 90% of the limit so that we only need to
+    /// evict once every few thousand new clients rather than on every request.
+    ///
+    /// Clients with a full bucket are indistinguishable from ones we've never seen so those go
+    /// first, followed by the least recently used buckets.
+    fn evict(&self, buckets: &mut HashMap<(Quota, String), Bucket>, now: Instant) {
+        buckets.retain(|(quota, _), bucket| {
+            // Refill a copy so that we don't lose track of when the bucket was last used
+            let per_minute = self.limits.per_minute(*quota);
+            let mut refilled = *bucket;
+            refilled.refill(per_minute, now);
+            refilled.tokens < per_minute as f64
+        });
+
+        let target = self.max_buckets - self.max_buckets / 10;
+        if buckets.len() <= target {
+            return;
+        }
+
+        let excess = buckets.len() - target;
+        let mut by_age: Vec<_> = buckets
+            .iter()
+            .map(|(key, bucket)| (bucket.updated, key.clone()))
+            .collect();
+        by_age.select_nth_unstable_by_key(excess - 1, |(updated, _)| *updated);
+        for (_, key) in by_age.into_iter().take(excess) {
+            buckets.remove(&key);
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct RateLimitLayer {
+    limiter: Arc<RateLimiter>,
+}
+
+impl RateLimitLayer {
+    pub fn new(limits: RateLimits) -> Self {
+        Self {
+            limiter: Arc::new(RateLimiter::new(limits)),
+        }
+    }
+}
+
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimitMiddleware<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimitMiddleware {
+            inner,
+            limiter: self.limiter.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct RateLimitMiddleware<S> {
+    inner: S,
+    limiter: Arc<RateLimiter>,
+}
+
+impl<S, T, U> Service<Request<T>> for RateLimitMiddleware<S>
+where
+    S: Service<Request<T>, Response = Response<U>, Error = BoxError> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    T: Send + 'static,
+{
+    type Response = Response<U>;
+    type Error = BoxError;
+    type Future = BoxFuture<Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<T>) -> Self::Future {
+        // See: https://docs.rs/tower/latest/tower/trait.Service.html#be-careful-when-cloning-inner-services
+        let clone = self.inner.clone();
+        let mut inner = replace(&mut self.inner, clone);
+
+        // Without a peer address there is no way to tell clients apart, and lumping them all
+        // together would let one client lock out everyone else, so we don't limit them at all.
+        // This only happens if the server isn't listening on a TCP socket.
+        let path = req.uri().path();
+        let Some(key) = client_key(&req) else {
+            return Box::pin(async move { inner.call(req).await });
+        };
+
+        if let Err(wait) = self.limiter.check(path, &key, Instant::now()) {
+            metrics()
+                .rate_limited_requests
+                .with_label_values(&[method_label(path)])
+                .inc();
+
+            let mut status = Status::resource_exhausted("rate limit exceeded");
+            let retry_after = wait.as_secs_f64().ceil() as u64;
+            status
+                .metadata_mut()
+                .insert("retry-after", MetadataValue::from(retry_after));
+
+            return Box::pin(async move { Err(Box::new(status) as BoxError) });
+        }
+
+        Box::pin(async move { inner.call(req).await })
+    }
+}
+
+/// Identify the client making a request: authenticated requests have already had their claims
+/// attached by the auth middleware, otherwise we fall back to the peer address.
+fn client_key<T>(req: &Request<T>) -> Option<String> {
+    let is_public = PUBLIC_PATHS.iter().any(|s| req.uri().path().ends_with(s));
+
+    match req.extensions().get::<Claims>() {
+        Some(claims) if !is_public => Some(format!("sub:{}", claims.sub)),
+        _ => peer_addr(req).map(|addr| format!("ip:{}", addr.ip())),
+    }
+}
+
+/// The address of the connected peer, which tonic attaches to requests as a different type
+/// depending on whether or not TLS is enabled.
+fn peer_addr<T>(req: &Request<T>) -> Option<SocketAddr> {
+    let extensions = req.extensions();
+
+    extensions
+        .get::<TcpConnectInfo>()
+        .and_then(|info| info.remote_addr())
+        .or_else(|| {
+            extensions
+                .get::<TlsConnectInfo<TcpConnectInfo>>()
+                .and_then(|info| info.get_ref().remote_addr())
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VOTE: &str = "/ratings.features.user.User/Vote";
+    const AUTHENTICATE: &str = "/ratings.features.user.User/Authenticate";
+    const CHART: &str = "/ratings.features.chart.Chart/GetChart";
+
+    fn limiter() -> RateLimiter {
+        RateLimiter::new(RateLimits {
+            authenticate_per_minute: 2,
+            vote_per_minute: 60,
+            default_per_minute: 0,
+        })
+    }
+
+    #[test]
+    fn requests_over_the_quota_are_rejected() {
+        let limiter = limiter();
+        let now = Instant::now();
+
+        assert!(limiter.check(AUTHENTICATE, "a", now).is_ok());
+        assert!(limiter.check(AUTHENTICATE, "a", now).is_ok());
+        let wait = limiter.check(AUTHENTICATE, "a", now).unwrap_err();
+
+        assert_eq!(wait, Duration::from_secs(30));
+    }
+
+    #[test]
+    fn clients_and_quotas_are_limited_independently() {
+        let limiter = limiter();
+        let now = Instant::now();
+
+        for _ in 0..2 {
+            limiter.check(AUTHENTICATE, "a", now).unwrap();
+        }
+
+        assert!(limiter.check(AUTHENTICATE, "b", now).is_ok());
+        assert!(limiter.check(VOTE, "a", now).is_ok());
+    }
+
+    #[test]
+    fn buckets_refill_over_time() {
+        let limiter = limiter();
+        let now = Instant::now();
+
+        for _ in 0..60 {
+            limiter.check(VOTE, "a", now).unwrap();
+        }
+        assert!(limiter.check(VOTE, "a", now).is_err());
+
+        assert!(limiter
+            .check(VOTE, "a", now + Duration::from_secs(1))
+            .is_ok());
+    }
+
+    #[test]
+    fn least_recently_used_buckets_are_evicted() {
+        let mut limiter = limiter();
+        limiter.max_buckets = 10;
+        let now = Instant::now();
+
+        for i in 0..10 {
+            let at = now + Duration::from_millis(i);
+            limiter.check(VOTE, &format!("client-{i}"), at).unwrap();
+        }
+        limiter
+            .check(VOTE, "new-client", now + Duration::from_millis(10))
+            .unwrap();
+
+        let buckets = limiter.buckets.lock().unwrap();
+        assert_eq!(buckets.len(), 10);
+        assert!(!buckets.contains_key(&(Quota::Vote, "client-0".to_string())));
+        assert!(buckets.contains_key(&(Quota::Vote, "client-1".to_string())));
+        assert!(buckets.contains_key(&(Quota::Vote, "new-client".to_string())));
+    }
+
+    #[test]
+    fn plain_tcp_clients_are_keyed_by_peer_ip() {
+        let mut req = Request::post(AUTHENTICATE).body(()).unwrap();
+        req.extensions_mut().insert(TcpConnectInfo {
+            local_addr: None,
+            remote_addr: Some("10.0.0.1:1234".parse().unwrap()),
+        });
+
+        assert_eq!(client_key(&req).as_deref(), Some("ip:10.0.0.1"));
+    }
+
+    #[tokio::test]
+    async fn tls_clients_are_keyed_by_peer_ip() {
+        let mut req = Request::post(AUTHENTICATE).body(()).unwrap();
+        req.extensions_mut().insert(tls_connect_info().await);
+
+        assert_eq!(client_key(&req).as_deref(), Some("ip:127.0.0.1"));
+    }
+
+    #[test]
+    fn clients_without_a_peer_address_have_no_key() {
+        let req = Request::post(AUTHENTICATE).body(()).unwrap();
+
+        assert_eq!(client_key(&req), None);
+    }
+
+    /// Complete a TLS handshake over a local TCP connection to get hold of the connection info
+    /// tonic attaches to requests when TLS is enabled.
+    async fn tls_connect_info() -> TlsConnectInfo<TcpConnectInfo> {
+        use tokio::net::{TcpListener, TcpStream};
+        use tokio_rustls::{
+            rustls::{
+                pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer, ServerName},
+                ClientConfig, RootCertStore, ServerConfig,
+            },
+            TlsAcceptor, TlsConnector,
+        };
+        use tonic::transport::server::Connected;
+
+        let cert = CertificateDer::from_pem_slice(include_bytes!("../../tests/tls/localhost.pem"))
+            .unwrap();
+        let key =
+            PrivateKeyDer::from_pem_slice(include_bytes!("../../tests/tls/localhost.key")).unwrap();
+        let ca = CertificateDer::from_pem_slice(include_bytes!("../../tests/tls/ca.pem")).unwrap();
+
+        let server_config = ServerConfig::builder()
+            .with_no_client_auth()
+            .with_single_cert(vec![cert], key)
+            .unwrap();
+        let mut roots = RootCertStore::empty();
+        roots.add(ca).unwrap();
+        let client_config = ClientConfig::builder()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let client = tokio::spawn(async move {
+            let stream = TcpStream::connect(addr).await.unwrap();
+            let name = ServerName::try_from("localhost").unwrap();
+            TlsConnector::from(Arc::new(client_config))
+                .connect(name, stream)
+                .await
+                .unwrap()
+        });
+
+        let (stream, _) = listener.accept().await.unwrap();
+        let server = TlsAcceptor::from(Arc::new(server_config))
+            .accept(stream)
+            .await
+            .unwrap();
+        let _client = client.await.unwrap();
+
+        server.connect_info()
+    }
+
+    #[test]
+    fn a_zero_quota_is_unlimited() {
+        let limiter = limiter();
+        let now = Instant::now();
+
+        for _ in 0..1000 {
+            assert!(limiter.check(CHART, "a", now).is_ok());
+        }
+    }
+}
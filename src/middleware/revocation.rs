@@ -0,0 +1,188 @@
+//! A custom Tower [Layer] for rejecting requests made with tokens that have been revoked.
+//!
+//! Checking for revocation can need a DB lookup so this sits after rate limiting, relying on the
+//! claims attached to the request by the [`AuthLayer`](super::AuthLayer). Lookups are cached
+//! (see [`cache::token_revocations`]) so most requests are served without touching the DB.
+use crate::{
+    cache, conn,
+    db::{self, User},
+    jwt::Claims,
+    middleware::{BoxError, BoxFuture},
+};
+use http::{Request, Response};
+use std::{
+    mem::replace,
+    task::{Context, Poll},
+    time::Duration,
+};
+use time::OffsetDateTime;
+use tokio::time::{interval, MissedTickBehavior};
+use tonic::Status;
+use tower::{Layer, Service};
+use tracing::{error, info};
+
+/// How often revocations that can no longer affect any token are deleted
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Clone, Default)]
+pub struct RevocationLayer;
+
+impl<S> Layer<S> for RevocationLayer {
+    type Service = RevocationMiddleware<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RevocationMiddleware { inner }
+    }
+}
+
+#[derive(Clone)]
+pub struct RevocationMiddleware<S> {
+    inner: S,
+}
+
+impl<S, T, U> Service<Request<T>> for RevocationMiddleware<S>
+where
+    S: Service<Request<T>, Response = Response<U>, Error = BoxError> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    T: Send + 'static,
+{
+    type Response = Response<U>;
+    type Error = BoxError;
+    type Future = BoxFuture<Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<T>) -> Self::Future {
+        // See: https://docs.rs/tower/latest/tower/trait.Service.html#be-careful-when-cloning-inner-services
+        let clone = self.inner.clone();
+        let mut inner = replace(&mut self.inner, clone);
+
+        // Public paths don't have claims attached so there is nothing to check
+        let Some(claims) = req.extensions().get::<Claims>().cloned() else {
+            return Box::pin(async move { inner.call(req).await });
+        };
+
+        Box::pin(async move {
+            match is_revoked(&claims).await {
+                Ok(false) => inner.call(req).await,
+                Ok(true) => {
+                    Err(Box::new(Status::unauthenticated("token has been revoked")) as BoxError)
+                }
+                Err(e) => {
+                    error!("unable to check for token revocation: {e}");
+                    Err(Box::new(Status::unavailable("unable to verify token")) as BoxError)
+                }
+            }
+        })
+    }
+}
+
+/// Tokens issued before a user deleted their account are revoked so that they can't be used to
+/// act on behalf of a user that no longer exists.
+///
+/// Tokens only record the second they were issued in, so those issued in the same second as a
+/// revocation are treated as revoked too.
+async fn is_revoked(claims: &Claims) -> db::Result<bool> {
+    let issued_at = OffsetDateTime::from_unix_timestamp(claims.iat as i64)
+        .unwrap_or(OffsetDateTime::UNIX_EPOCH);
+
+    let revoked_at = match cache::token_revocations().get(&claims.sub) {
+        Some(revoked_at) => revoked_at,
+        None => lookup_revocation(&claims.sub).await?,
+    };
+
+    Ok(revoked_at.is_some_and(|revoked_at| revoked_at >= issued_at))
+}
+
+/// Look up when tokens for the given client were last revoked, caching the result.
+///
+/// Revoking tokens drops the client from the cache once committed, which can happen between our
+/// lookup and caching the result. Looking again after caching catches that: if the two lookups
+/// disagree the cached entry may be stale so it is dropped again.
+async fn lookup_revocation(client_hash: &str) -> db::Result<Option<OffsetDateTime>> {
+    let conn = conn!();
+    let revoked_at = User::tokens_revoked_at(client_hash, conn).await?;
+    cache::token_revocations().insert(client_hash.to_string(), revoked_at);
+
+    let rechecked = User::tokens_revoked_at(client_hash, conn).await?;
+    if rechecked != revoked_at {
+        cache::token_revocations().remove(client_hash);
+    }
+
+    Ok(rechecked)
+}
+
+/// Periodically delete revocations made more than `max_token_lifetime` ago, along with the
+/// records of used refresh tokens that have expired: every token they apply to has expired by
+/// then.
+///
+/// This runs until the process exits so should be spawned as its own task.
+pub async fn prune_revocations_periodically(max_token_lifetime: Duration) {
+    let mut interval = interval(PRUNE_INTERVAL);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    loop {
+        interval.tick().await;
+        let before = OffsetDateTime::now_utc() - max_token_lifetime;
+        match prune_revocations(before).await {
+            Ok((revocations, refresh_tokens)) => {
+                info!(
+                    revocations,
+                    refresh_tokens, "pruned expired token revocations"
+                )
+            }
+            Err(e) => error!("unable to prune token revocations: {e}"),
+        }
+    }
+}
+
+async fn prune_revocations(before: OffsetDateTime) -> db::Result<(u64, u64)> {
+    let conn = conn!();
+    let revocations = User::prune_token_revocations(before, conn).await?;
+    let refresh_tokens = User::prune_used_refresh_tokens(conn).await?;
+
+    Ok((revocations, refresh_tokens))
+}
+
+#[cfg(all(test, not(feature = "skip_cache")))]
+mod tests {
+    use super::*;
+    use crate::jwt::TokenType;
+
+    fn claims_issued_at(sub: &str, iat: OffsetDateTime) -> Claims {
+        Claims {
+            iat: iat.unix_timestamp() as usize,
+            ..Claims::new(
+                sub.to_string(),
+                "test".to_string(),
+                TokenType::Access,
+                time::Duration::minutes(5),
+            )
+        }
+    }
+
+    #[tokio::test]
+    async fn cached_revocations_are_checked_without_the_db() {
+        let revoked_at = OffsetDateTime::now_utc().replace_nanosecond(0).unwrap();
+        cache::token_revocations().insert("revoked".to_string(), Some(revoked_at));
+        cache::token_revocations().insert("never-revoked".to_string(), None);
+
+        let before = revoked_at - time::Duration::minutes(1);
+        let after = revoked_at + time::Duration::minutes(1);
+
+        assert!(is_revoked(&claims_issued_at("revoked", before))
+            .await
+            .unwrap());
+        assert!(is_revoked(&claims_issued_at("revoked", revoked_at))
+            .await
+            .unwrap());
+        assert!(!is_revoked(&claims_issued_at("revoked", after))
+            .await
+            .unwrap());
+        assert!(!is_revoked(&claims_issued_at("never-revoked", before))
+            .await
+            .unwrap());
+    }
+}
@@ -1,6 +1,12 @@
+pub mod admin {
+    include!("ratings.features.admin.rs");
+}
 pub mod app {
     include!("ratings.features.app.rs");
 }
+pub mod publisher {
+    include!("ratings.features.publisher.rs");
+}
 pub mod user {
     include!("ratings.features.user.rs");
 }
@@ -10,3 +16,6 @@ pub mod common {
 pub mod chart {
     include!("ratings.features.chart.rs");
 }
+
+/// The encoded descriptors for all of our protobuf definitions, used for server reflection
+pub const FILE_DESCRIPTOR_SET: &[u8] = tonic::include_file_descriptor_set!("ratings_descriptor");
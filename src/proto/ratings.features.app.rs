@@ -11,6 +11,140 @@ pub struct GetRatingResponse {
     #[prost(message, optional, tag = "1")]
     pub rating: ::core::option::Option<super::common::Rating>,
 }
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetRatingsRequest {
+    #[prost(string, repeated, tag = "1")]
+    pub snap_ids: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetRatingsResponse {
+    /// In the order the snaps were requested, with duplicates removed
+    #[prost(message, repeated, tag = "1")]
+    pub ratings: ::prost::alloc::vec::Vec<super::common::Rating>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetRatingByRevisionRequest {
+    #[prost(string, tag = "1")]
+    pub snap_id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetRatingByRevisionResponse {
+    #[prost(string, tag = "1")]
+    pub snap_id: ::prost::alloc::string::String,
+    /// Ordered from the most recent revision to the oldest
+    #[prost(message, repeated, tag = "2")]
+    pub revisions: ::prost::alloc::vec::Vec<RevisionRating>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RevisionRating {
+    #[prost(int32, tag = "1")]
+    pub snap_revision: i32,
+    #[prost(uint64, tag = "2")]
+    pub total_votes: u64,
+    #[prost(uint64, tag = "3")]
+    pub positive_votes: u64,
+    #[prost(float, tag = "4")]
+    pub raw_rating: f32,
+    #[prost(enumeration = "super::common::RatingsBand", tag = "5")]
+    pub ratings_band: i32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListReviewsRequest {
+    #[prost(string, tag = "1")]
+    pub snap_id: ::prost::alloc::string::String,
+    /// Only list reviews of this revision
+    #[prost(int32, optional, tag = "2")]
+    pub snap_revision: ::core::option::Option<i32>,
+    #[prost(uint32, optional, tag = "3")]
+    pub page_size: ::core::option::Option<u32>,
+    #[prost(string, tag = "4")]
+    pub page_token: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListReviewsResponse {
+    /// Ordered from the most recently submitted review to the oldest
+    #[prost(message, repeated, tag = "1")]
+    pub reviews: ::prost::alloc::vec::Vec<Review>,
+    /// Empty when there are no further pages
+    #[prost(string, tag = "2")]
+    pub next_page_token: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Review {
+    #[prost(int32, tag = "1")]
+    pub id: i32,
+    #[prost(string, tag = "2")]
+    pub snap_id: ::prost::alloc::string::String,
+    #[prost(int32, tag = "3")]
+    pub snap_revision: i32,
+    #[prost(string, tag = "4")]
+    pub body: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "5")]
+    pub created: ::core::option::Option<::prost_types::Timestamp>,
+    #[prost(message, optional, tag = "6")]
+    pub updated: ::core::option::Option<::prost_types::Timestamp>,
+    /// The reviewer's vote on the reviewed revision, if they voted on it
+    #[prost(bool, optional, tag = "7")]
+    pub vote_up: ::core::option::Option<bool>,
+    /// Unset if the snap's publisher has not replied
+    #[prost(message, optional, tag = "8")]
+    pub reply: ::core::option::Option<PublisherReply>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PublisherReply {
+    #[prost(string, tag = "1")]
+    pub publisher_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub body: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "3")]
+    pub created: ::core::option::Option<::prost_types::Timestamp>,
+    #[prost(message, optional, tag = "4")]
+    pub updated: ::core::option::Option<::prost_types::Timestamp>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetFeedbackBreakdownRequest {
+    #[prost(string, tag = "1")]
+    pub snap_id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetFeedbackBreakdownResponse {
+    #[prost(string, tag = "1")]
+    pub snap_id: ::prost::alloc::string::String,
+    /// The reasons given across all revisions
+    #[prost(message, repeated, tag = "2")]
+    pub reasons: ::prost::alloc::vec::Vec<ReasonCount>,
+    /// Ordered from the most recent revision to the oldest, only including revisions where reasons
+    /// were given
+    #[prost(message, repeated, tag = "3")]
+    pub revisions: ::prost::alloc::vec::Vec<RevisionFeedback>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RevisionFeedback {
+    #[prost(int32, tag = "1")]
+    pub snap_revision: i32,
+    #[prost(message, repeated, tag = "2")]
+    pub reasons: ::prost::alloc::vec::Vec<ReasonCount>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ReasonCount {
+    #[prost(enumeration = "super::common::VoteReason", tag = "1")]
+    pub reason: i32,
+    #[prost(uint64, tag = "2")]
+    pub votes: u64,
+}
 /// Generated client implementations.
 pub mod app_client {
     #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
@@ -121,6 +255,110 @@ pub mod app_client {
                 .insert(GrpcMethod::new("ratings.features.app.App", "GetRating"));
             self.inner.unary(req, path, codec).await
         }
+        pub async fn get_rating_by_revision(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetRatingByRevisionRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetRatingByRevisionResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/ratings.features.app.App/GetRatingByRevision",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("ratings.features.app.App", "GetRatingByRevision"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn get_ratings(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetRatingsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetRatingsResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/ratings.features.app.App/GetRatings",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("ratings.features.app.App", "GetRatings"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn list_reviews(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ListReviewsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListReviewsResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/ratings.features.app.App/ListReviews",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("ratings.features.app.App", "ListReviews"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn get_feedback_breakdown(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetFeedbackBreakdownRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetFeedbackBreakdownResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/ratings.features.app.App/GetFeedbackBreakdown",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("ratings.features.app.App", "GetFeedbackBreakdown"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
     }
 }
 /// Generated server implementations.
@@ -137,6 +375,34 @@ pub mod app_server {
             tonic::Response<super::GetRatingResponse>,
             tonic::Status,
         >;
+        async fn get_rating_by_revision(
+            &self,
+            request: tonic::Request<super::GetRatingByRevisionRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetRatingByRevisionResponse>,
+            tonic::Status,
+        >;
+        async fn get_ratings(
+            &self,
+            request: tonic::Request<super::GetRatingsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetRatingsResponse>,
+            tonic::Status,
+        >;
+        async fn list_reviews(
+            &self,
+            request: tonic::Request<super::ListReviewsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListReviewsResponse>,
+            tonic::Status,
+        >;
+        async fn get_feedback_breakdown(
+            &self,
+            request: tonic::Request<super::GetFeedbackBreakdownRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetFeedbackBreakdownResponse>,
+            tonic::Status,
+        >;
     }
     #[derive(Debug)]
     pub struct AppServer<T: App> {
@@ -261,6 +527,186 @@ pub mod app_server {
                     };
                     Box::pin(fut)
                 }
+                "/ratings.features.app.App/GetRatingByRevision" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetRatingByRevisionSvc<T: App>(pub Arc<T>);
+                    impl<
+                        T: App,
+                    > tonic::server::UnaryService<super::GetRatingByRevisionRequest>
+                    for GetRatingByRevisionSvc<T> {
+                        type Response = super::GetRatingByRevisionResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetRatingByRevisionRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as App>::get_rating_by_revision(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetRatingByRevisionSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/ratings.features.app.App/GetRatings" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetRatingsSvc<T: App>(pub Arc<T>);
+                    impl<T: App> tonic::server::UnaryService<super::GetRatingsRequest>
+                    for GetRatingsSvc<T> {
+                        type Response = super::GetRatingsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetRatingsRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as App>::get_ratings(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetRatingsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/ratings.features.app.App/ListReviews" => {
+                    #[allow(non_camel_case_types)]
+                    struct ListReviewsSvc<T: App>(pub Arc<T>);
+                    impl<T: App> tonic::server::UnaryService<super::ListReviewsRequest>
+                    for ListReviewsSvc<T> {
+                        type Response = super::ListReviewsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ListReviewsRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as App>::list_reviews(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ListReviewsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/ratings.features.app.App/GetFeedbackBreakdown" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetFeedbackBreakdownSvc<T: App>(pub Arc<T>);
+                    impl<
+                        T: App,
+                    > tonic::server::UnaryService<super::GetFeedbackBreakdownRequest>
+                    for GetFeedbackBreakdownSvc<T> {
+                        type Response = super::GetFeedbackBreakdownResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetFeedbackBreakdownRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as App>::get_feedback_breakdown(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetFeedbackBreakdownSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 _ => {
                     Box::pin(async move {
                         Ok(
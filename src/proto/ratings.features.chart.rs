@@ -6,6 +6,24 @@ pub struct GetChartRequest {
     pub timeframe: i32,
     #[prost(enumeration = "Category", optional, tag = "2")]
     pub category: ::core::option::Option<i32>,
+    /// The maximum number of entries to return, the server default is used if unset
+    #[prost(uint32, optional, tag = "3")]
+    pub page_size: ::core::option::Option<u32>,
+    /// The next_page_token from a previous response, used to fetch the following page
+    #[prost(string, tag = "4")]
+    pub page_token: ::prost::alloc::string::String,
+    /// Only include snaps with at least this many votes matching the other filters
+    #[prost(uint32, optional, tag = "5")]
+    pub min_votes: ::core::option::Option<u32>,
+    /// Only include votes cast on this snap revision or later
+    #[prost(int32, optional, tag = "6")]
+    pub min_revision: ::core::option::Option<i32>,
+    /// Only include votes cast on this snap revision or earlier
+    #[prost(int32, optional, tag = "7")]
+    pub max_revision: ::core::option::Option<i32>,
+    /// How snaps should be ranked, defaulting to their rating
+    #[prost(enumeration = "ChartType", tag = "8")]
+    pub chart_type: i32,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -16,10 +34,16 @@ pub struct GetChartResponse {
     pub ordered_chart_data: ::prost::alloc::vec::Vec<ChartData>,
     #[prost(enumeration = "Category", optional, tag = "3")]
     pub category: ::core::option::Option<i32>,
+    /// Set if there are further entries in the chart, empty otherwise
+    #[prost(string, tag = "4")]
+    pub next_page_token: ::prost::alloc::string::String,
+    #[prost(enumeration = "ChartType", tag = "5")]
+    pub chart_type: i32,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct ChartData {
+    /// The rating score of the snap or, for trending charts, its trend score
     #[prost(float, tag = "1")]
     pub raw_rating: f32,
     #[prost(message, optional, tag = "2")]
@@ -27,10 +51,43 @@ pub struct ChartData {
 }
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
 #[repr(i32)]
+pub enum ChartType {
+    /// Snaps ranked by their rating
+    TopRated = 0,
+    /// Snaps ranked by growth in positive votes compared with the previous timeframe.
+    /// Trending charts require a bounded timeframe and default to a week if unspecified.
+    Trending = 1,
+}
+impl ChartType {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            ChartType::TopRated => "CHART_TYPE_TOP_RATED",
+            ChartType::Trending => "CHART_TYPE_TRENDING",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "CHART_TYPE_TOP_RATED" => Some(Self::TopRated),
+            "CHART_TYPE_TRENDING" => Some(Self::Trending),
+            _ => None,
+        }
+    }
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
 pub enum Timeframe {
     Unspecified = 0,
     Week = 1,
     Month = 2,
+    Day = 3,
+    Quarter = 4,
+    Year = 5,
+    AllTime = 6,
 }
 impl Timeframe {
     /// String value of the enum field names used in the ProtoBuf definition.
@@ -42,6 +99,10 @@ impl Timeframe {
             Timeframe::Unspecified => "TIMEFRAME_UNSPECIFIED",
             Timeframe::Week => "TIMEFRAME_WEEK",
             Timeframe::Month => "TIMEFRAME_MONTH",
+            Timeframe::Day => "TIMEFRAME_DAY",
+            Timeframe::Quarter => "TIMEFRAME_QUARTER",
+            Timeframe::Year => "TIMEFRAME_YEAR",
+            Timeframe::AllTime => "TIMEFRAME_ALL_TIME",
         }
     }
     /// Creates an enum from field names used in the ProtoBuf definition.
@@ -50,6 +111,10 @@ impl Timeframe {
             "TIMEFRAME_UNSPECIFIED" => Some(Self::Unspecified),
             "TIMEFRAME_WEEK" => Some(Self::Week),
             "TIMEFRAME_MONTH" => Some(Self::Month),
+            "TIMEFRAME_DAY" => Some(Self::Day),
+            "TIMEFRAME_QUARTER" => Some(Self::Quarter),
+            "TIMEFRAME_YEAR" => Some(Self::Year),
+            "TIMEFRAME_ALL_TIME" => Some(Self::AllTime),
             _ => None,
         }
     }
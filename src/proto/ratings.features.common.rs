@@ -10,6 +10,10 @@ pub struct Rating {
     pub ratings_band: i32,
     #[prost(string, tag = "4")]
     pub snap_name: ::prost::alloc::string::String,
+    /// The ratings band calculated with older votes weighted less than recent ones.
+    /// This matches ratings_band when vote decay is disabled.
+    #[prost(enumeration = "RatingsBand", tag = "5")]
+    pub decayed_ratings_band: i32,
 }
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
 #[repr(i32)]
@@ -49,3 +53,79 @@ impl RatingsBand {
         }
     }
 }
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum ReportReason {
+    Unspecified = 0,
+    Spam = 1,
+    Abuse = 2,
+    OffTopic = 3,
+    Misleading = 4,
+    Other = 5,
+}
+impl ReportReason {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            ReportReason::Unspecified => "REPORT_REASON_UNSPECIFIED",
+            ReportReason::Spam => "REPORT_REASON_SPAM",
+            ReportReason::Abuse => "REPORT_REASON_ABUSE",
+            ReportReason::OffTopic => "REPORT_REASON_OFF_TOPIC",
+            ReportReason::Misleading => "REPORT_REASON_MISLEADING",
+            ReportReason::Other => "REPORT_REASON_OTHER",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "REPORT_REASON_UNSPECIFIED" => Some(Self::Unspecified),
+            "REPORT_REASON_SPAM" => Some(Self::Spam),
+            "REPORT_REASON_ABUSE" => Some(Self::Abuse),
+            "REPORT_REASON_OFF_TOPIC" => Some(Self::OffTopic),
+            "REPORT_REASON_MISLEADING" => Some(Self::Misleading),
+            "REPORT_REASON_OTHER" => Some(Self::Other),
+            _ => None,
+        }
+    }
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum VoteReason {
+    Unspecified = 0,
+    Crashes = 1,
+    MissingFeatures = 2,
+    Performance = 3,
+    Outdated = 4,
+    Other = 5,
+}
+impl VoteReason {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            VoteReason::Unspecified => "VOTE_REASON_UNSPECIFIED",
+            VoteReason::Crashes => "VOTE_REASON_CRASHES",
+            VoteReason::MissingFeatures => "VOTE_REASON_MISSING_FEATURES",
+            VoteReason::Performance => "VOTE_REASON_PERFORMANCE",
+            VoteReason::Outdated => "VOTE_REASON_OUTDATED",
+            VoteReason::Other => "VOTE_REASON_OTHER",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "VOTE_REASON_UNSPECIFIED" => Some(Self::Unspecified),
+            "VOTE_REASON_CRASHES" => Some(Self::Crashes),
+            "VOTE_REASON_MISSING_FEATURES" => Some(Self::MissingFeatures),
+            "VOTE_REASON_PERFORMANCE" => Some(Self::Performance),
+            "VOTE_REASON_OUTDATED" => Some(Self::Outdated),
+            "VOTE_REASON_OTHER" => Some(Self::Other),
+            _ => None,
+        }
+    }
+}
@@ -11,6 +11,22 @@ pub struct AuthenticateRequest {
 pub struct AuthenticateResponse {
     #[prost(string, tag = "1")]
     pub token: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub refresh_token: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RefreshTokenRequest {
+    #[prost(string, tag = "1")]
+    pub refresh_token: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RefreshTokenResponse {
+    #[prost(string, tag = "1")]
+    pub token: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub refresh_token: ::prost::alloc::string::String,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -37,6 +53,8 @@ pub struct Vote {
     pub timestamp: ::core::option::Option<::prost_types::Timestamp>,
     #[prost(string, tag = "5")]
     pub snap_name: ::prost::alloc::string::String,
+    #[prost(enumeration = "super::common::VoteReason", repeated, tag = "6")]
+    pub reasons: ::prost::alloc::vec::Vec<i32>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -47,6 +65,59 @@ pub struct VoteRequest {
     pub snap_revision: i32,
     #[prost(bool, tag = "3")]
     pub vote_up: bool,
+    /// Why the snap was voted down, only allowed for negative votes
+    #[prost(enumeration = "super::common::VoteReason", repeated, tag = "4")]
+    pub reasons: ::prost::alloc::vec::Vec<i32>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RetractVoteRequest {
+    #[prost(string, tag = "1")]
+    pub snap_id: ::prost::alloc::string::String,
+    #[prost(int32, tag = "2")]
+    pub snap_revision: i32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RetractVoteResponse {
+    /// Whether there was a vote to retract
+    #[prost(bool, tag = "1")]
+    pub retracted: bool,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SubmitReviewRequest {
+    #[prost(string, tag = "1")]
+    pub snap_id: ::prost::alloc::string::String,
+    /// The revision being reviewed, replacing that of any previous review of the snap
+    #[prost(int32, tag = "2")]
+    pub snap_revision: i32,
+    #[prost(string, tag = "3")]
+    pub body: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeleteReviewRequest {
+    #[prost(string, tag = "1")]
+    pub snap_id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeleteReviewResponse {
+    /// Whether there was a review to delete
+    #[prost(bool, tag = "1")]
+    pub deleted: bool,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ReportContentRequest {
+    /// The ID of the review being reported, as returned from ListReviews
+    #[prost(int32, tag = "1")]
+    pub review_id: i32,
+    #[prost(enumeration = "super::common::ReportReason", tag = "2")]
+    pub reason: i32,
+    #[prost(string, tag = "3")]
+    pub details: ::prost::alloc::string::String,
 }
 /// Generated client implementations.
 pub mod user_client {
@@ -158,6 +229,31 @@ pub mod user_client {
                 .insert(GrpcMethod::new("ratings.features.user.User", "Authenticate"));
             self.inner.unary(req, path, codec).await
         }
+        pub async fn refresh_token(
+            &mut self,
+            request: impl tonic::IntoRequest<super::RefreshTokenRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::RefreshTokenResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/ratings.features.user.User/RefreshToken",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("ratings.features.user.User", "RefreshToken"));
+            self.inner.unary(req, path, codec).await
+        }
         pub async fn delete(
             &mut self,
             request: impl tonic::IntoRequest<()>,
@@ -202,6 +298,31 @@ pub mod user_client {
                 .insert(GrpcMethod::new("ratings.features.user.User", "Vote"));
             self.inner.unary(req, path, codec).await
         }
+        pub async fn retract_vote(
+            &mut self,
+            request: impl tonic::IntoRequest<super::RetractVoteRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::RetractVoteResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/ratings.features.user.User/RetractVote",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("ratings.features.user.User", "RetractVote"));
+            self.inner.unary(req, path, codec).await
+        }
         pub async fn get_snap_votes(
             &mut self,
             request: impl tonic::IntoRequest<super::GetSnapVotesRequest>,
@@ -227,6 +348,75 @@ pub mod user_client {
                 .insert(GrpcMethod::new("ratings.features.user.User", "GetSnapVotes"));
             self.inner.unary(req, path, codec).await
         }
+        pub async fn submit_review(
+            &mut self,
+            request: impl tonic::IntoRequest<super::SubmitReviewRequest>,
+        ) -> std::result::Result<tonic::Response<()>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/ratings.features.user.User/SubmitReview",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("ratings.features.user.User", "SubmitReview"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn delete_review(
+            &mut self,
+            request: impl tonic::IntoRequest<super::DeleteReviewRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::DeleteReviewResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/ratings.features.user.User/DeleteReview",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("ratings.features.user.User", "DeleteReview"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn report_content(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ReportContentRequest>,
+        ) -> std::result::Result<tonic::Response<()>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/ratings.features.user.User/ReportContent",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("ratings.features.user.User", "ReportContent"));
+            self.inner.unary(req, path, codec).await
+        }
     }
 }
 /// Generated server implementations.
@@ -243,6 +433,13 @@ pub mod user_server {
             tonic::Response<super::AuthenticateResponse>,
             tonic::Status,
         >;
+        async fn refresh_token(
+            &self,
+            request: tonic::Request<super::RefreshTokenRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::RefreshTokenResponse>,
+            tonic::Status,
+        >;
         async fn delete(
             &self,
             request: tonic::Request<()>,
@@ -251,6 +448,13 @@ pub mod user_server {
             &self,
             request: tonic::Request<super::VoteRequest>,
         ) -> std::result::Result<tonic::Response<()>, tonic::Status>;
+        async fn retract_vote(
+            &self,
+            request: tonic::Request<super::RetractVoteRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::RetractVoteResponse>,
+            tonic::Status,
+        >;
         async fn get_snap_votes(
             &self,
             request: tonic::Request<super::GetSnapVotesRequest>,
@@ -258,6 +462,21 @@ pub mod user_server {
             tonic::Response<super::GetSnapVotesResponse>,
             tonic::Status,
         >;
+        async fn submit_review(
+            &self,
+            request: tonic::Request<super::SubmitReviewRequest>,
+        ) -> std::result::Result<tonic::Response<()>, tonic::Status>;
+        async fn delete_review(
+            &self,
+            request: tonic::Request<super::DeleteReviewRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::DeleteReviewResponse>,
+            tonic::Status,
+        >;
+        async fn report_content(
+            &self,
+            request: tonic::Request<super::ReportContentRequest>,
+        ) -> std::result::Result<tonic::Response<()>, tonic::Status>;
     }
     #[derive(Debug)]
     pub struct UserServer<T: User> {
@@ -382,6 +601,50 @@ pub mod user_server {
                     };
                     Box::pin(fut)
                 }
+                "/ratings.features.user.User/RefreshToken" => {
+                    #[allow(non_camel_case_types)]
+                    struct RefreshTokenSvc<T: User>(pub Arc<T>);
+                    impl<T: User> tonic::server::UnaryService<super::RefreshTokenRequest>
+                    for RefreshTokenSvc<T> {
+                        type Response = super::RefreshTokenResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::RefreshTokenRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as User>::refresh_token(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = RefreshTokenSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 "/ratings.features.user.User/Delete" => {
                     #[allow(non_camel_case_types)]
                     struct DeleteSvc<T: User>(pub Arc<T>);
@@ -466,6 +729,50 @@ pub mod user_server {
                     };
                     Box::pin(fut)
                 }
+                "/ratings.features.user.User/RetractVote" => {
+                    #[allow(non_camel_case_types)]
+                    struct RetractVoteSvc<T: User>(pub Arc<T>);
+                    impl<T: User> tonic::server::UnaryService<super::RetractVoteRequest>
+                    for RetractVoteSvc<T> {
+                        type Response = super::RetractVoteResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::RetractVoteRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as User>::retract_vote(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = RetractVoteSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 "/ratings.features.user.User/GetSnapVotes" => {
                     #[allow(non_camel_case_types)]
                     struct GetSnapVotesSvc<T: User>(pub Arc<T>);
@@ -510,6 +817,140 @@ pub mod user_server {
                     };
                     Box::pin(fut)
                 }
+                "/ratings.features.user.User/SubmitReview" => {
+                    #[allow(non_camel_case_types)]
+                    struct SubmitReviewSvc<T: User>(pub Arc<T>);
+                    impl<T: User> tonic::server::UnaryService<super::SubmitReviewRequest>
+                    for SubmitReviewSvc<T> {
+                        type Response = ();
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::SubmitReviewRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as User>::submit_review(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = SubmitReviewSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/ratings.features.user.User/DeleteReview" => {
+                    #[allow(non_camel_case_types)]
+                    struct DeleteReviewSvc<T: User>(pub Arc<T>);
+                    impl<T: User> tonic::server::UnaryService<super::DeleteReviewRequest>
+                    for DeleteReviewSvc<T> {
+                        type Response = super::DeleteReviewResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::DeleteReviewRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as User>::delete_review(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = DeleteReviewSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/ratings.features.user.User/ReportContent" => {
+                    #[allow(non_camel_case_types)]
+                    struct ReportContentSvc<T: User>(pub Arc<T>);
+                    impl<
+                        T: User,
+                    > tonic::server::UnaryService<super::ReportContentRequest>
+                    for ReportContentSvc<T> {
+                        type Response = ();
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ReportContentRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as User>::report_content(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ReportContentSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 _ => {
                     Box::pin(async move {
                         Ok(
@@ -1,29 +1,32 @@
 //! Updating snap categories from data in snapcraft.io
 use crate::{
-    db::{set_categories_for_snap, snap_has_categories, Category},
-    ratings::{get_json, get_snap_name, Error},
+    conn,
+    db::{
+        replace_categories_for_snap, set_categories_for_snap, snap_has_categories, Category, Vote,
+    },
+    ratings::{get_snap_name, Error, SnapcraftClient},
     Context,
 };
+use futures::{stream, StreamExt};
 use serde::Deserialize;
-use sqlx::PgConnection;
-use std::sync::Arc;
-use tokio::sync::Notify;
-use tracing::error;
+use std::{sync::Arc, time::Duration};
+use time::OffsetDateTime;
+use tokio::{
+    sync::Notify,
+    time::{interval_at, Instant, MissedTickBehavior},
+};
+use tracing::{error, info};
 
 /// Update the categories for a given snap.
 ///
 /// In the case where we do not have categories, we need to fetch them and store them in the DB.
 /// This is racey without coordination so we check to see if any other tasks are currently attempting
 /// this and block on them completing if they are, if not then we set up the Notify and they block on us.
-pub async fn update_categories(
-    snap_id: &str,
-    ctx: &Context,
-    conn: &mut PgConnection,
-) -> Result<(), Error> {
+pub async fn update_categories(snap_id: &str, ctx: &Context) -> Result<(), Error> {
     // If we have categories for the requested snap in place already then skip updating.
-    // Eventually we will need to update and refresh categories over time but the assumption for now is
-    // that snap categories do not change frequently so we do not need to eagerly update them.
-    if snap_has_categories(snap_id, conn).await? {
+    // Snap categories do not change frequently so we leave keeping them up to date to the
+    // background refresh task (see `refresh_categories_periodically`).
+    if snap_has_categories(snap_id, conn!()).await? {
         return Ok(());
     }
 
@@ -50,8 +53,7 @@ pub async fn update_categories(
     // We can't early return while holding the Notifier as that will leave any waiting tasks
     // blocked. Rather than attempt to retry at this stage we allow for stale category data
     // until a new task attempts to get data for the same snap.
-    let base = &ctx.config.snapcraft_io_uri;
-    if let Err(e) = update_categories_inner(snap_id, base, &ctx.http_client, conn).await {
+    if let Err(e) = update_categories_inner(snap_id, ctx).await {
         error!(%snap_id, "unable to update snap categories: {e}");
     }
 
@@ -65,36 +67,87 @@ pub async fn update_categories(
 }
 
 #[inline]
-async fn update_categories_inner(
-    snap_id: &str,
-    base: &str,
-    client: &reqwest::Client,
-    conn: &mut PgConnection,
-) -> Result<(), Error> {
-    let categories = get_snap_categories(snap_id, base, client).await?;
+async fn update_categories_inner(snap_id: &str, ctx: &Context) -> Result<(), Error> {
+    let Some(snap_name) = get_snap_name(snap_id, ctx).await? else {
+        return Ok(());
+    };
+    let categories = get_snap_categories(&snap_name, &ctx.snapcraft).await?;
     if !categories.is_empty() {
-        set_categories_for_snap(snap_id, categories, conn).await?;
+        set_categories_for_snap(snap_id, categories, conn!()).await?;
     }
 
     Ok(())
 }
 
-/// Pull snap categories by for a given snapd_id from the snapcraft.io rest API
-async fn get_snap_categories(
+/// Periodically re-sync the categories of all snaps that have been voted on recently.
+///
+/// This runs until the process exits so should be spawned as its own task.
+pub async fn refresh_categories_periodically(ctx: Arc<Context>) {
+    let period = Duration::from_secs(ctx.config.category_refresh_interval_secs);
+    let mut interval = interval_at(Instant::now() + period, period);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    loop {
+        interval.tick().await;
+        if let Err(e) = refresh_categories(&ctx).await {
+            error!("unable to refresh snap categories: {e}");
+        }
+    }
+}
+
+/// Re-sync the categories of all snaps that have been voted on within the configured window,
+/// replacing any existing categories we have stored for them.
+async fn refresh_categories(ctx: &Context) -> Result<(), Error> {
+    let window = time::Duration::days(ctx.config.category_refresh_window_days.into());
+    let snap_ids =
+        Vote::get_snap_ids_voted_since(OffsetDateTime::now_utc() - window, conn!()).await?;
+    info!(n_snaps = snap_ids.len(), "refreshing snap categories");
+
+    stream::iter(snap_ids)
+        .for_each_concurrent(
+            ctx.config.category_refresh_concurrency,
+            |snap_id| async move {
+                if let Err(e) = refresh_categories_for_snap(&snap_id, ctx).await {
+                    error!(%snap_id, "unable to refresh snap categories: {e}");
+                }
+            },
+        )
+        .await;
+
+    Ok(())
+}
+
+/// Re-sync the categories of a single snap from snapcraft.io, replacing any existing categories
+/// we have stored for it. Returns the new categories or `None` if the snap is unknown.
+///
+/// A DB connection is only taken once the categories have been fetched, so that slow responses
+/// from snapcraft.io don't tie up the pool.
+pub async fn refresh_categories_for_snap(
     snap_id: &str,
-    base: &str,
-    client: &reqwest::Client,
-) -> Result<Vec<Category>, Error> {
-    let snap_name = get_snap_name(snap_id, base, client).await?;
+    ctx: &Context,
+) -> Result<Option<Vec<Category>>, Error> {
+    let Some(snap_name) = get_snap_name(snap_id, ctx).await? else {
+        return Ok(None);
+    };
+    let categories = get_snap_categories(&snap_name, &ctx.snapcraft).await?;
+    replace_categories_for_snap(snap_id, categories.clone(), conn!()).await?;
 
-    let base_url = reqwest::Url::parse(base).map_err(|e| Error::InvalidUrl(e.to_string()))?;
-    let info_url = base_url
-        .join(&format!("snaps/info/{snap_name}"))
-        .map_err(|e| Error::InvalidUrl(e.to_string()))?;
+    Ok(Some(categories))
+}
 
+/// Pull snap categories by for a given snap_name from the snapcraft.io rest API
+async fn get_snap_categories(
+    snap_name: &str,
+    client: &SnapcraftClient,
+) -> Result<Vec<Category>, Error> {
     let FindResp {
         snap: SnapInfo { categories },
-    } = get_json(info_url, &[("fields", "categories")], client).await?;
+    } = client
+        .get_json(
+            &format!("snaps/info/{snap_name}"),
+            &[("fields", "categories")],
+        )
+        .await?;
 
     let res: Result<Vec<Category>, Error> = categories
         .into_iter()
@@ -130,10 +183,8 @@ mod tests {
     #[ignore = "hits snapcraft.io"]
     #[tokio::test]
     async fn get_snap_categories_works() {
-        let client = reqwest::Client::new();
-        let base = "https://api.snapcraft.io/v2/";
-        let snap_id = "NeoQngJVBf2wKC48bxnF2xqmfEFGdVnx"; // steam
-        let categories = get_snap_categories(snap_id, base, &client).await.unwrap();
+        let client = SnapcraftClient::new("https://api.snapcraft.io/v2/").unwrap();
+        let categories = get_snap_categories("steam", &client).await.unwrap();
 
         assert_eq!(categories, vec![Category::Games]);
     }
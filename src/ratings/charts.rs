@@ -1,10 +1,28 @@
 //! Struct definitions for the charting feature for ratings.
 use crate::{
-    db::{Timeframe, VoteSummary},
-    ratings::rating::{calculate_band, Rating},
+    db::{Timeframe, TrendingVoteSummary, VoteSummary},
+    ratings::rating::{Rating, RatingCalculator},
 };
 use std::cmp::Ordering;
 
+/// The number of positive votes added to the previous window when calculating how quickly a
+/// snap is trending, so that a handful of new votes for a snap without any previous votes
+/// does not outrank sustained growth for a popular snap.
+const TREND_SMOOTHING: f64 = 10.0;
+
+/// The different ways in which snaps can be ranked within a [`Chart`].
+///
+/// The discriminants match the `ChartType` enum in the chart protobuf definition.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, strum::FromRepr)]
+#[repr(i32)]
+pub enum ChartType {
+    /// Snaps ordered by their rating score
+    #[default]
+    TopRated = 0,
+    /// Snaps ordered by the growth in positive votes compared with the previous timeframe
+    Trending = 1,
+}
+
 #[derive(Debug, Clone)]
 pub struct Chart {
     pub timeframe: Timeframe,
@@ -12,19 +30,118 @@ pub struct Chart {
 }
 
 impl Chart {
-    pub fn new(timeframe: Timeframe, data: Vec<VoteSummary>) -> Self {
-        let mut data: Vec<ChartData> = data.into_iter().map(Into::into).collect();
+    pub fn new(
+        timeframe: Timeframe,
+        data: Vec<VoteSummary>,
+        calculator: &RatingCalculator,
+    ) -> Self {
+        let data = data
+            .into_iter()
+            .map(|summary| ChartData::from_summary(summary, calculator))
+            .collect();
+
+        Self::from_unsorted(timeframe, data)
+    }
+
+    /// Creates a chart ranking snaps by how quickly they are gaining positive votes.
+    pub fn trending(
+        timeframe: Timeframe,
+        data: Vec<TrendingVoteSummary>,
+        calculator: &RatingCalculator,
+    ) -> Self {
+        let data = data
+            .into_iter()
+            .map(|summary| ChartData::from_trending_summary(summary, calculator))
+            .collect();
+
+        Self::from_unsorted(timeframe, data)
+    }
 
+    fn from_unsorted(timeframe: Timeframe, mut data: Vec<ChartData>) -> Self {
+        // Ties are broken on the snap ID so that the ordering is stable between pages
         data.sort_by(|a, b| {
             b.raw_rating
                 .partial_cmp(&a.raw_rating)
                 .unwrap_or(Ordering::Equal)
+                .then_with(|| a.rating.snap_id.cmp(&b.rating.snap_id))
         });
 
-        // Take only the first 20 elements from the sorted chart_data
-        Chart {
-            timeframe,
-            data: data.into_iter().take(20).collect(),
+        Chart { timeframe, data }
+    }
+
+    /// Splits out a page of at most `page_size` entries from the chart, starting immediately
+    /// after the entry identified by `after` if it is provided. A cursor is returned alongside
+    /// the page if there are further entries remaining in the chart.
+    pub fn into_page(
+        self,
+        page_size: usize,
+        after: Option<&ChartCursor>,
+    ) -> (Vec<ChartData>, Option<ChartCursor>) {
+        let start = match after {
+            Some(cursor) => self.data.partition_point(|d| !cursor.precedes(d)),
+            None => 0,
+        };
+        let remaining = self.data.len().saturating_sub(start);
+
+        let page: Vec<ChartData> = self.data.into_iter().skip(start).take(page_size).collect();
+        let next = match page.last() {
+            Some(last) if remaining > page.len() => Some(ChartCursor::from(last)),
+            _ => None,
+        };
+
+        (page, next)
+    }
+}
+
+/// The position of an entry within a [`Chart`], used for paging through the chart.
+///
+/// Cursors identify an entry by its score and snap ID rather than its index so that paging
+/// remains consistent if the chart changes between requests.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChartCursor {
+    raw_rating: f32,
+    snap_id: String,
+}
+
+impl ChartCursor {
+    /// Encodes the cursor as an opaque page token.
+    pub fn encode(&self) -> String {
+        format!("{:08x}{}", self.raw_rating.to_bits(), self.snap_id)
+    }
+
+    /// Decodes a page token previously returned from [`encode`][Self::encode].
+    pub fn decode(token: &str) -> Option<Self> {
+        if token.len() <= 8 || !token.is_char_boundary(8) {
+            return None;
+        }
+        let (bits, snap_id) = token.split_at(8);
+        let raw_rating = f32::from_bits(u32::from_str_radix(bits, 16).ok()?);
+
+        Some(Self {
+            raw_rating,
+            snap_id: snap_id.to_string(),
+        })
+    }
+
+    /// Whether the given entry comes after this cursor in chart order.
+    fn precedes(&self, data: &ChartData) -> bool {
+        match self
+            .raw_rating
+            .partial_cmp(&data.raw_rating)
+            .unwrap_or(Ordering::Equal)
+        {
+            Ordering::Greater => true,
+            Ordering::Less => false,
+            Ordering::Equal => data.rating.snap_id > self.snap_id,
+        }
+    }
+}
+
+impl From<&ChartData> for ChartCursor {
+    fn from(data: &ChartData) -> Self {
+        Self {
+            raw_rating: data.raw_rating,
+            snap_id: data.rating.snap_id.clone(),
         }
     }
 }
@@ -35,16 +152,113 @@ pub struct ChartData {
     pub rating: Rating,
 }
 
-impl From<VoteSummary> for ChartData {
-    fn from(vote_summary: VoteSummary) -> Self {
-        let (raw_rating, ratings_band) = calculate_band(&vote_summary);
-        let rating = Rating {
-            snap_id: vote_summary.snap_id,
-            total_votes: vote_summary.total_votes as u64,
-            ratings_band,
-        };
+impl ChartData {
+    pub fn from_summary(vote_summary: VoteSummary, calculator: &RatingCalculator) -> Self {
+        let (raw_rating, _) = calculator.calculate_band(&vote_summary);
+        let rating = Rating::from_summary(vote_summary, calculator);
         let raw_rating = raw_rating.unwrap_or(0.0) as f32;
 
         Self { raw_rating, rating }
     }
+
+    /// Creates chart data where the raw rating is the trend score of the snap rather than its
+    /// rating score.
+    pub fn from_trending_summary(
+        trending_summary: TrendingVoteSummary,
+        calculator: &RatingCalculator,
+    ) -> Self {
+        let TrendingVoteSummary {
+            summary,
+            current_positive_votes,
+            previous_positive_votes,
+        } = trending_summary;
+        let raw_rating = trend_score(current_positive_votes, previous_positive_votes) as f32;
+        let rating = Rating::from_summary(summary, calculator);
+
+        Self { raw_rating, rating }
+    }
+}
+
+/// The growth in positive votes between two windows relative to the (smoothed) number of
+/// positive votes in the previous window.
+fn trend_score(current_positive_votes: i64, previous_positive_votes: i64) -> f64 {
+    (current_positive_votes - previous_positive_votes) as f64
+        / (previous_positive_votes as f64 + TREND_SMOOTHING)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chart(ratings: &[(&str, f32)]) -> Chart {
+        let data = ratings
+            .iter()
+            .map(|&(snap_id, raw_rating)| ChartData {
+                raw_rating,
+                rating: Rating {
+                    snap_id: snap_id.to_string(),
+                    ..Default::default()
+                },
+            })
+            .collect();
+
+        Chart {
+            timeframe: Timeframe::Unspecified,
+            data,
+        }
+    }
+
+    fn ids(data: &[ChartData]) -> Vec<&str> {
+        data.iter().map(|d| d.rating.snap_id.as_str()).collect()
+    }
+
+    #[test]
+    fn paging_through_a_chart_returns_every_entry_once() {
+        let c = chart(&[("a", 0.9), ("b", 0.8), ("c", 0.8), ("d", 0.5), ("e", 0.1)]);
+
+        let (page, next) = c.clone().into_page(2, None);
+        assert_eq!(ids(&page), vec!["a", "b"]);
+
+        let (page, next) = c.clone().into_page(2, next.as_ref());
+        assert_eq!(ids(&page), vec!["c", "d"]);
+
+        let (page, next) = c.into_page(2, next.as_ref());
+        assert_eq!(ids(&page), vec!["e"]);
+        assert!(next.is_none());
+    }
+
+    #[test]
+    fn no_cursor_is_returned_for_an_exactly_full_last_page() {
+        let c = chart(&[("a", 0.9), ("b", 0.8)]);
+        let (page, next) = c.into_page(2, None);
+
+        assert_eq!(ids(&page), vec!["a", "b"]);
+        assert!(next.is_none());
+    }
+
+    #[test]
+    fn trend_score_favours_growth() {
+        assert!(trend_score(20, 10) > trend_score(10, 10));
+        assert!(trend_score(10, 10) > trend_score(5, 10));
+        assert_eq!(trend_score(10, 10), 0.0);
+    }
+
+    #[test]
+    fn trend_score_is_smoothed_for_new_snaps() {
+        // A couple of votes for a previously unknown snap should not outrank a popular snap
+        // that has seen substantial growth
+        assert!(trend_score(2, 0) < trend_score(200, 100));
+    }
+
+    #[test]
+    fn cursors_round_trip_through_page_tokens() {
+        let cursor = ChartCursor {
+            raw_rating: 0.123,
+            snap_id: "NeoQngJVBf2wKC48bxnF2xqmfEFGdVnx".to_string(),
+        };
+
+        assert_eq!(ChartCursor::decode(&cursor.encode()), Some(cursor));
+        assert_eq!(ChartCursor::decode("not a token"), None);
+        assert_eq!(ChartCursor::decode(""), None);
+    }
 }
@@ -1,8 +1,12 @@
 //! Calculations around snap ratings based on received votes
-use crate::db::VoteSummary;
+use crate::{db::VoteSummary, Config};
+use serde::Deserialize;
 
-/// An arbitrary fixed number of votes we've determined is below the threshold to be meaningful.
-const INSUFFICIENT_VOTES_QUANTITY: i64 = 25;
+/// The default number of votes below which we consider a rating not to be meaningful.
+pub const DEFAULT_MIN_VOTES: i64 = 25;
+
+/// The default Z-score used by the [`WilsonScorer`], giving a ~95% confidence.
+pub const DEFAULT_Z_SCORE: f64 = 1.96;
 
 /// A descriptive mapping of a number of ratings to a general indicator of "how good"
 /// an app can be said to be.
@@ -19,25 +23,23 @@ pub enum RatingsBand {
 }
 
 impl RatingsBand {
-    /// The percentage of votes that denotes an upper bound between good and very good
-    const GOOD_UPPER: f64 = 0.8;
-    /// The percentage of votes that denotes the line between neutral and good
-    const NEUTRAL_UPPER: f64 = 0.55;
-    /// The percentage of votes that denotes the line between poor and neutral
-    const POOR_UPPER: f64 = 0.45;
-    /// The percentage of votes that denotes a line between poor and very poor
-    const VERY_POOR_UPPER: f64 = 0.2;
-
-    /// Converts a raw value into a [`RatingsBand`] value by comparing it with the associated
-    /// constants of the struct.
-    pub fn from_value(value: f64) -> RatingsBand {
-        if value > Self::GOOD_UPPER {
+    /// Converts a raw value into a [`RatingsBand`] value by comparing it with the given
+    /// [`BandThresholds`].
+    pub fn from_value(value: f64, thresholds: &BandThresholds) -> RatingsBand {
+        let BandThresholds {
+            good_upper,
+            neutral_upper,
+            poor_upper,
+            very_poor_upper,
+        } = *thresholds;
+
+        if value > good_upper {
             RatingsBand::VeryGood
-        } else if value <= Self::GOOD_UPPER && value > Self::NEUTRAL_UPPER {
+        } else if value <= good_upper && value > neutral_upper {
             RatingsBand::Good
-        } else if value <= Self::NEUTRAL_UPPER && value > Self::POOR_UPPER {
+        } else if value <= neutral_upper && value > poor_upper {
             RatingsBand::Neutral
-        } else if value <= Self::POOR_UPPER && value > Self::VERY_POOR_UPPER {
+        } else if value <= poor_upper && value > very_poor_upper {
             RatingsBand::Poor
         } else {
             RatingsBand::VeryPoor
@@ -45,6 +47,30 @@ impl RatingsBand {
     }
 }
 
+/// The cut-off values used to map a raw score onto a [`RatingsBand`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BandThresholds {
+    /// The score that denotes an upper bound between good and very good
+    pub good_upper: f64,
+    /// The score that denotes the line between neutral and good
+    pub neutral_upper: f64,
+    /// The score that denotes the line between poor and neutral
+    pub poor_upper: f64,
+    /// The score that denotes a line between poor and very poor
+    pub very_poor_upper: f64,
+}
+
+impl Default for BandThresholds {
+    fn default() -> Self {
+        Self {
+            good_upper: 0.8,
+            neutral_upper: 0.55,
+            poor_upper: 0.45,
+            very_poor_upper: 0.2,
+        }
+    }
+}
+
 impl PartialOrd for RatingsBand {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
         if matches!(self, RatingsBand::InsufficientVotes)
@@ -68,32 +94,184 @@ pub struct Rating {
     /// The descriptive indicator of "how good" this snap is based
     /// on aggregated ratings.
     pub ratings_band: RatingsBand,
+    /// As with `ratings_band` but with older votes weighted less than recent ones. This is
+    /// the same as `ratings_band` if vote decay is not enabled.
+    pub decayed_ratings_band: RatingsBand,
 }
 
-impl From<VoteSummary> for Rating {
-    fn from(votes: VoteSummary) -> Self {
-        let (_, ratings_band) = calculate_band(&votes);
+impl Rating {
+    /// Builds the [`Rating`] for a snap from its [`VoteSummary`] using the given calculator.
+    pub fn from_summary(votes: VoteSummary, calculator: &RatingCalculator) -> Self {
+        let (_, ratings_band) = calculator.calculate_band(&votes);
+        let (_, decayed_ratings_band) = calculator.calculate_decayed_band(&votes);
 
         Self {
             snap_id: votes.snap_id,
             total_votes: votes.total_votes as u64,
             ratings_band,
+            decayed_ratings_band,
+        }
+    }
+}
+
+/// The available [`RatingScorer`] implementations that can be selected through [`Config`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ScorerKind {
+    /// See [`WilsonScorer`]
+    #[default]
+    Wilson,
+    /// See [`BayesianAverageScorer`]
+    BayesianAverage,
+    /// See [`RatioScorer`]
+    Ratio,
+}
+
+/// An algorithm for reducing the votes for a snap down to a single score in the range 0..=1,
+/// where a higher score denotes a better rated snap.
+pub trait RatingScorer: Send + Sync {
+    /// Vote counts are given as floating point values as votes may be weighted by their age.
+    fn score(&self, positive_votes: f64, total_votes: f64) -> f64;
+}
+
+/// Scores snaps using the lower bound of the Wilson score confidence interval.
+///
+/// See [`confidence_interval_lower_bound`] for details.
+#[derive(Debug, Clone, Copy)]
+pub struct WilsonScorer {
+    pub z_score: f64,
+}
+
+impl Default for WilsonScorer {
+    fn default() -> Self {
+        Self {
+            z_score: DEFAULT_Z_SCORE,
+        }
+    }
+}
+
+impl RatingScorer for WilsonScorer {
+    fn score(&self, positive_votes: f64, total_votes: f64) -> f64 {
+        confidence_interval_lower_bound(positive_votes, total_votes, self.z_score)
+    }
+}
+
+/// Scores snaps using a Bayesian average: every snap is treated as having received
+/// `prior_weight` additional votes with a positive ratio of `prior_mean`, pulling snaps with
+/// few votes towards the prior.
+#[derive(Debug, Clone, Copy)]
+pub struct BayesianAverageScorer {
+    pub prior_mean: f64,
+    pub prior_weight: f64,
+}
+
+impl RatingScorer for BayesianAverageScorer {
+    fn score(&self, positive_votes: f64, total_votes: f64) -> f64 {
+        let denominator = total_votes + self.prior_weight;
+        if denominator <= 0.0 {
+            return 0.0;
         }
+
+        (positive_votes + self.prior_mean * self.prior_weight) / denominator
     }
 }
 
-/// Converts a given [`VoteSummary`] into a [`RatingsBand`], if applicable, along with a
-/// confidence interval if applicable.
-pub fn calculate_band(votes: &VoteSummary) -> (Option<f64>, RatingsBand) {
-    if votes.total_votes < INSUFFICIENT_VOTES_QUANTITY {
-        return (None, RatingsBand::InsufficientVotes);
+/// Scores snaps using the plain ratio of positive votes to total votes.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RatioScorer;
+
+impl RatingScorer for RatioScorer {
+    fn score(&self, positive_votes: f64, total_votes: f64) -> f64 {
+        if total_votes <= 0.0 {
+            return 0.0;
+        }
+
+        positive_votes / total_votes
     }
-    let adjusted_ratio = confidence_interval_lower_bound(votes.positive_votes, votes.total_votes);
+}
 
-    (
-        Some(adjusted_ratio),
-        RatingsBand::from_value(adjusted_ratio),
-    )
+/// Converts [`VoteSummary`] values into scores and [`RatingsBand`]s using a configurable
+/// [`RatingScorer`], minimum number of votes and set of [`BandThresholds`].
+pub struct RatingCalculator {
+    scorer: Box<dyn RatingScorer>,
+    min_votes: i64,
+    thresholds: BandThresholds,
+}
+
+impl Default for RatingCalculator {
+    fn default() -> Self {
+        Self::new(
+            Box::new(WilsonScorer::default()),
+            DEFAULT_MIN_VOTES,
+            BandThresholds::default(),
+        )
+    }
+}
+
+impl RatingCalculator {
+    pub fn new(scorer: Box<dyn RatingScorer>, min_votes: i64, thresholds: BandThresholds) -> Self {
+        Self {
+            scorer,
+            min_votes,
+            thresholds,
+        }
+    }
+
+    /// Creates a new calculator using the scorer and parameters selected in the given [`Config`].
+    pub fn from_config(config: &Config) -> Self {
+        let scorer: Box<dyn RatingScorer> = match config.rating_scorer {
+            ScorerKind::Wilson => Box::new(WilsonScorer {
+                z_score: config.rating_z_score,
+            }),
+            ScorerKind::BayesianAverage => Box::new(BayesianAverageScorer {
+                prior_mean: config.rating_bayesian_prior_mean,
+                prior_weight: config.rating_bayesian_prior_weight,
+            }),
+            ScorerKind::Ratio => Box::new(RatioScorer),
+        };
+
+        let thresholds = BandThresholds {
+            good_upper: config.rating_band_good_upper,
+            neutral_upper: config.rating_band_neutral_upper,
+            poor_upper: config.rating_band_poor_upper,
+            very_poor_upper: config.rating_band_very_poor_upper,
+        };
+
+        Self::new(scorer, config.rating_min_votes, thresholds)
+    }
+
+    /// Converts a given [`VoteSummary`] into a [`RatingsBand`], if applicable, along with the
+    /// raw score if there are enough votes for it to be meaningful.
+    pub fn calculate_band(&self, votes: &VoteSummary) -> (Option<f64>, RatingsBand) {
+        if votes.total_votes < self.min_votes {
+            return (None, RatingsBand::InsufficientVotes);
+        }
+        let score = self
+            .scorer
+            .score(votes.positive_votes as f64, votes.total_votes as f64);
+
+        (
+            Some(score),
+            RatingsBand::from_value(score, &self.thresholds),
+        )
+    }
+
+    /// As [`calculate_band`][Self::calculate_band] but using the decayed vote totals of the
+    /// [`VoteSummary`]. Whether there are sufficient votes is still determined by the
+    /// number of votes actually cast.
+    pub fn calculate_decayed_band(&self, votes: &VoteSummary) -> (Option<f64>, RatingsBand) {
+        if votes.total_votes < self.min_votes {
+            return (None, RatingsBand::InsufficientVotes);
+        }
+        let score = self
+            .scorer
+            .score(votes.decayed_positive_votes, votes.decayed_total_votes);
+
+        (
+            Some(score),
+            RatingsBand::from_value(score, &self.thresholds),
+        )
+    }
 }
 
 /// Calculates the Lower Bound of Wilson Score Confidence Interval for Ranking Snaps
@@ -106,20 +284,18 @@ pub fn calculate_band(votes: &VoteSummary) -> (Option<f64>, RatingsBand) {
 ///
 /// Algorithm:
 /// Starts with the observed proportion of positive ratings, adjusts it based on
-/// total ratings, and incorporates a 95% confidence interval Z-score for
-/// uncertainty.
+/// total ratings, and incorporates the Z-score for the desired confidence interval
+/// (1.96 for ~95%) to account for uncertainty.
 ///
 /// References:
 /// - https://www.evanmiller.org/how-not-to-sort-by-average-rating.html
 /// - https://en.wikipedia.org/wiki/Binomial_proportion_confidence_interval#Wilson_score_interval
-fn confidence_interval_lower_bound(positive_ratings: i64, total_ratings: i64) -> f64 {
-    if total_ratings == 0 {
+fn confidence_interval_lower_bound(positive_ratings: f64, total_ratings: f64, z_score: f64) -> f64 {
+    if total_ratings <= 0.0 {
         return 0.0;
     }
 
-    let z_score: f64 = 1.96; // hardcoded for a ~95% confidence
-    let total_ratings = total_ratings as f64;
-    let positive_ratings_ratio = positive_ratings as f64 / total_ratings;
+    let positive_ratings_ratio = positive_ratings / total_ratings;
     ((positive_ratings_ratio + (z_score * z_score) / (2.0 * total_ratings))
         - z_score
             * f64::sqrt(
@@ -136,7 +312,7 @@ mod tests {
 
     #[test]
     fn test_zero() {
-        let lower_bound = confidence_interval_lower_bound(0, 0);
+        let lower_bound = confidence_interval_lower_bound(0.0, 0.0, DEFAULT_Z_SCORE);
         assert_eq!(
             lower_bound, 0.0,
             "Lower bound should be 0.0 when there are 0 votes"
@@ -149,9 +325,13 @@ mod tests {
         let mut last_lower_bound = 0.0;
 
         for total_ratings in (100..1000).step_by(100) {
-            let positive_ratings = (total_ratings as f64 * ratio).round() as i64;
-            let new_lower_bound = confidence_interval_lower_bound(positive_ratings, total_ratings);
-            let raw_positive_ratio = positive_ratings as f64 / total_ratings as f64;
+            let positive_ratings = (total_ratings as f64 * ratio).round();
+            let new_lower_bound = confidence_interval_lower_bound(
+                positive_ratings,
+                total_ratings as f64,
+                DEFAULT_Z_SCORE,
+            );
+            let raw_positive_ratio = positive_ratings / total_ratings as f64;
 
             // As the total ratings increase, the new lower bound should be closer to the raw positive ratio.
             assert!(
@@ -169,8 +349,10 @@ mod tests {
             snap_id: 1.to_string(),
             total_votes: 1,
             positive_votes: 1,
+            decayed_total_votes: 1.0,
+            decayed_positive_votes: 1.0,
         };
-        let (rating, band) = calculate_band(&votes);
+        let (rating, band) = RatingCalculator::default().calculate_band(&votes);
         assert_eq!(
             band,
             RatingsBand::InsufficientVotes,
@@ -188,8 +370,10 @@ mod tests {
             snap_id: 1.to_string(),
             total_votes: 100,
             positive_votes: 100,
+            decayed_total_votes: 100.0,
+            decayed_positive_votes: 100.0,
         };
-        let (rating, band) = calculate_band(&votes);
+        let (rating, band) = RatingCalculator::default().calculate_band(&votes);
         assert_eq!(
             band,
             RatingsBand::VeryGood,
@@ -200,4 +384,86 @@ mod tests {
             "Should return fairly positive raw rating for this ration and volume of positive votes."
         )
     }
+
+    #[test]
+    fn test_min_votes_is_configurable() {
+        let votes = VoteSummary {
+            snap_id: 1.to_string(),
+            total_votes: 5,
+            positive_votes: 5,
+            decayed_total_votes: 5.0,
+            decayed_positive_votes: 5.0,
+        };
+        let calculator = RatingCalculator::new(Box::new(RatioScorer), 5, BandThresholds::default());
+        let (rating, band) = calculator.calculate_band(&votes);
+
+        assert_eq!(rating, Some(1.0));
+        assert_eq!(band, RatingsBand::VeryGood);
+    }
+
+    #[test]
+    fn test_band_thresholds_are_configurable() {
+        let votes = VoteSummary {
+            snap_id: 1.to_string(),
+            total_votes: 100,
+            positive_votes: 70,
+            decayed_total_votes: 100.0,
+            decayed_positive_votes: 70.0,
+        };
+        let thresholds = BandThresholds {
+            good_upper: 0.6,
+            ..Default::default()
+        };
+        let calculator = RatingCalculator::new(Box::new(RatioScorer), 25, thresholds);
+        let (_, band) = calculator.calculate_band(&votes);
+
+        assert_eq!(band, RatingsBand::VeryGood);
+    }
+
+    #[test]
+    fn test_higher_z_score_is_more_conservative() {
+        let relaxed = WilsonScorer { z_score: 1.0 }.score(40.0, 50.0);
+        let strict = WilsonScorer { z_score: 2.58 }.score(40.0, 50.0);
+
+        assert!(strict < relaxed, "{strict} should be less than {relaxed}");
+    }
+
+    #[test]
+    fn test_bayesian_average_pulls_towards_prior() {
+        let scorer = BayesianAverageScorer {
+            prior_mean: 0.5,
+            prior_weight: 10.0,
+        };
+
+        assert_eq!(scorer.score(0.0, 0.0), 0.5);
+        assert_eq!(scorer.score(10.0, 10.0), 0.75);
+
+        let few = scorer.score(10.0, 10.0);
+        let many = scorer.score(1000.0, 1000.0);
+        assert!(many > few, "more votes should move further from the prior");
+    }
+
+    #[test]
+    fn test_ratio_scorer() {
+        assert_eq!(RatioScorer.score(0.0, 0.0), 0.0);
+        assert_eq!(RatioScorer.score(3.0, 4.0), 0.75);
+    }
+
+    #[test]
+    fn test_decayed_band_uses_weighted_votes() {
+        // 100 votes, of which most of the positive votes are old enough to carry little weight
+        let votes = VoteSummary {
+            snap_id: 1.to_string(),
+            total_votes: 100,
+            positive_votes: 60,
+            decayed_total_votes: 50.5,
+            decayed_positive_votes: 10.5,
+        };
+        let calculator = RatingCalculator::default();
+        let (_, band) = calculator.calculate_band(&votes);
+        let (_, decayed_band) = calculator.calculate_decayed_band(&votes);
+
+        assert_eq!(band, RatingsBand::Neutral);
+        assert_eq!(decayed_band, RatingsBand::VeryPoor);
+    }
 }
@@ -0,0 +1,277 @@
+//! A resilient client for the snapcraft.io API.
+//!
+//! Requests are made with a timeout and transient failures (timeouts, connection errors, 429s and
+//! 5xx responses) are retried with jittered exponential backoff. If snapcraft.io keeps failing then
+//! a circuit breaker trips and requests fail fast until the cooldown has elapsed, so that an outage
+//! doesn't stall every request we serve.
+use crate::{metrics::metrics, ratings::Error, Config};
+use rand::Rng;
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use tracing::warn;
+
+/// The default timeout for a single request to snapcraft.io
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+/// The default number of times a failed request is retried
+pub const DEFAULT_MAX_RETRIES: u32 = 2;
+/// The default delay before the first retry, later retries back off exponentially from this
+pub const DEFAULT_RETRY_BASE_DELAY: Duration = Duration::from_millis(100);
+/// The default number of consecutive failures before the circuit breaker opens
+pub const DEFAULT_BREAKER_THRESHOLD: u32 = 5;
+/// The default time the circuit breaker stays open before requests are attempted again
+pub const DEFAULT_BREAKER_COOLDOWN: Duration = Duration::from_secs(30);
+
+/// Client for making requests to the snapcraft.io API
+#[derive(Debug)]
+pub struct SnapcraftClient {
+    client: reqwest::Client,
+    base: String,
+    max_retries: u32,
+    retry_base_delay: Duration,
+    breaker: CircuitBreaker,
+}
+
+impl SnapcraftClient {
+    /// Create a new client for the API at `base` using the default retry and breaker settings.
+    pub fn new(base: impl Into<String>) -> Result<Self, reqwest::Error> {
+        Ok(Self {
+            client: build_http_client(DEFAULT_TIMEOUT)?,
+            base: base.into(),
+            max_retries: DEFAULT_MAX_RETRIES,
+            retry_base_delay: DEFAULT_RETRY_BASE_DELAY,
+            breaker: CircuitBreaker::new(DEFAULT_BREAKER_THRESHOLD, DEFAULT_BREAKER_COOLDOWN),
+        })
+    }
+
+    /// Create a new client from the snapcraft.io settings in the application config.
+    pub fn from_config(config: &Config) -> Result<Self, reqwest::Error> {
+        Ok(Self {
+            client: build_http_client(Duration::from_millis(config.snapcraft_timeout_ms))?,
+            base: config.snapcraft_io_uri.clone(),
+            max_retries: config.snapcraft_max_retries,
+            retry_base_delay: Duration::from_millis(config.snapcraft_retry_base_delay_ms),
+            breaker: CircuitBreaker::new(
+                config.snapcraft_breaker_threshold,
+                Duration::from_secs(config.snapcraft_breaker_cooldown_secs),
+            ),
+        })
+    }
+
+    /// GET the JSON resource at `path` relative to the base URL, retrying transient failures.
+    pub async fn get_json<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &[(&str, &str)],
+    ) -> Result<T, Error> {
+        let base_url =
+            reqwest::Url::parse(&self.base).map_err(|e| Error::InvalidUrl(e.to_string()))?;
+        let url = base_url
+            .join(path)
+            .map_err(|e| Error::InvalidUrl(e.to_string()))?;
+
+        if !self.breaker.allows_request() {
+            record_request("circuit_open", None);
+            return Err(Error::CircuitOpen);
+        }
+
+        let mut attempt = 0;
+        loop {
+            let start = Instant::now();
+            let res = self.get_text(url.clone(), query).await;
+            let outcome = match &res {
+                Ok(_) => "success",
+                Err(e) if is_transient(e) => "transient_error",
+                Err(_) => "error",
+            };
+            record_request(outcome, Some(start));
+
+            match res {
+                Ok(s) => {
+                    self.breaker.record_success();
+                    return Ok(serde_json::from_str(&s)?);
+                }
+
+                Err(e) if !is_transient(&e) => {
+                    // The API responded, it just wasn't what we wanted (e.g. a 404 for an
+                    // unknown snap) so there is nothing to gain from retrying.
+                    self.breaker.record_success();
+                    return Err(e.into());
+                }
+
+                Err(e) if attempt >= self.max_retries => {
+                    self.breaker.record_failure();
+                    return Err(e.into());
+                }
+
+                Err(e) => {
+                    let delay = self.backoff(attempt);
+                    warn!(%url, attempt, ?delay, "request to snapcraft.io failed, retrying: {e}");
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+            }
+        }
+    }
+
+    async fn get_text(
+        &self,
+        url: reqwest::Url,
+        query: &[(&str, &str)],
+    ) -> Result<String, reqwest::Error> {
+        self.client
+            .get(url)
+            .header("User-Agent", "ratings-service")
+            .header("Snap-Device-Series", 16)
+            .query(query)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await
+    }
+
+    /// Exponential backoff with full jitter: a random delay up to `base * 2^attempt`.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let max = self.retry_base_delay.saturating_mul(1 << attempt.min(16));
+        rand::thread_rng().gen_range(Duration::ZERO..=max)
+    }
+}
+
+fn record_request(outcome: &str, start: Option<Instant>) {
+    let m = metrics();
+    m.snapcraft_requests.with_label_values(&[outcome]).inc();
+    if let Some(start) = start {
+        m.snapcraft_request_duration
+            .with_label_values(&[outcome])
+            .observe(start.elapsed().as_secs_f64());
+    }
+}
+
+fn build_http_client(timeout: Duration) -> Result<reqwest::Client, reqwest::Error> {
+    reqwest::Client::builder()
+        .pool_idle_timeout(Duration::from_secs(5))
+        .timeout(timeout)
+        .build()
+}
+
+/// Whether a failed request is worth retrying and should count against the circuit breaker.
+fn is_transient(e: &reqwest::Error) -> bool {
+    match e.status() {
+        Some(status) => status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS,
+        None => e.is_timeout() || e.is_connect() || e.is_request(),
+    }
+}
+
+/// Tracks consecutive failed requests, refusing requests for a cooldown period once a threshold
+/// is reached. Once the cooldown has elapsed requests are allowed again: a single success closes
+/// the breaker while a further failure re-opens it.
+#[derive(Debug)]
+struct CircuitBreaker {
+    threshold: u32,
+    cooldown: Duration,
+    state: Mutex<BreakerState>,
+}
+
+#[derive(Debug, Default)]
+struct BreakerState {
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+}
+
+impl CircuitBreaker {
+    fn new(threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            threshold,
+            cooldown,
+            state: Default::default(),
+        }
+    }
+
+    fn allows_request(&self) -> bool {
+        let state = self.state.lock().unwrap();
+
+        match state.open_until {
+            Some(t) => Instant::now() >= t,
+            None => true,
+        }
+    }
+
+    fn record_success(&self) {
+        *self.state.lock().unwrap() = BreakerState::default();
+    }
+
+    fn record_failure(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.consecutive_failures += 1;
+
+        if state.consecutive_failures >= self.threshold {
+            if state.open_until.is_none() {
+                warn!(
+                    failures = state.consecutive_failures,
+                    "snapcraft.io circuit breaker opened"
+                );
+            }
+            state.open_until = Some(Instant::now() + self.cooldown);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn breaker_opens_after_threshold_failures() {
+        let breaker = CircuitBreaker::new(3, Duration::from_secs(60));
+
+        for _ in 0..2 {
+            breaker.record_failure();
+            assert!(breaker.allows_request());
+        }
+
+        breaker.record_failure();
+        assert!(!breaker.allows_request());
+    }
+
+    #[test]
+    fn success_resets_the_breaker() {
+        let breaker = CircuitBreaker::new(2, Duration::from_secs(60));
+
+        breaker.record_failure();
+        breaker.record_success();
+        breaker.record_failure();
+        assert!(breaker.allows_request());
+
+        breaker.record_failure();
+        breaker.record_success();
+        assert!(breaker.allows_request());
+    }
+
+    #[test]
+    fn breaker_allows_requests_after_cooldown() {
+        let breaker = CircuitBreaker::new(1, Duration::ZERO);
+
+        breaker.record_failure();
+        assert!(breaker.allows_request());
+
+        // Still over the threshold so a failed trial request re-opens the breaker
+        breaker.record_failure();
+        assert!(breaker.state.lock().unwrap().open_until.is_some());
+    }
+
+    #[test]
+    fn backoff_is_bounded_by_exponential_cap() {
+        let client = SnapcraftClient::new("http://localhost/").unwrap();
+
+        for attempt in 0..5 {
+            let cap = DEFAULT_RETRY_BASE_DELAY * 2u32.pow(attempt);
+            for _ in 0..20 {
+                assert!(client.backoff(attempt) <= cap);
+            }
+        }
+    }
+}
@@ -0,0 +1,150 @@
+//! Looking up snap metadata, backed by the snaps table and refreshed from snapcraft.io
+//!
+//! Requests to snapcraft.io can take a while once retries and timeouts are taken into account so
+//! the functions here take DB connections from the pool only for as long as they need them,
+//! rather than holding one across requests to snapcraft.io.
+use crate::{
+    conn,
+    db::Snap,
+    ratings::{Error, SnapcraftClient},
+    Context,
+};
+use futures::{stream, StreamExt};
+use reqwest::StatusCode;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use time::{Duration, OffsetDateTime};
+use tracing::warn;
+
+/// Look up the name of a single snap, returning `None` if it can't be determined.
+///
+/// See [`get_snap_names`] for details.
+pub async fn get_snap_name(snap_id: &str, ctx: &Context) -> Result<Option<String>, Error> {
+    let mut names = get_snap_names(&[snap_id.to_string()], ctx).await?;
+
+    Ok(names.remove(snap_id))
+}
+
+/// Look up the names of the given snaps, returning a map from snap ID to snap name.
+///
+/// Names are read from the snaps table where possible. Snaps we have no metadata for, or whose
+/// metadata is older than the configured TTL, are refreshed from snapcraft.io and the result is
+/// stored for future requests, with at most `snap_metadata_refresh_concurrency` requests to
+/// snapcraft.io in flight at once. If snapcraft.io can't be reached we fall back to stale metadata
+/// where we have it, and snaps we have never seen before are left out of the returned map so
+/// that callers can degrade gracefully rather than failing the whole request.
+pub async fn get_snap_names(
+    snap_ids: &[String],
+    ctx: &Context,
+) -> Result<HashMap<String, String>, Error> {
+    let stale_before =
+        OffsetDateTime::now_utc() - Duration::seconds(ctx.config.snap_metadata_ttl_secs as i64);
+
+    let known: HashMap<String, Snap> = Snap::get_by_snap_ids(snap_ids, conn!())
+        .await?
+        .into_iter()
+        .map(|snap| (snap.snap_id.clone(), snap))
+        .collect();
+
+    let mut names = HashMap::with_capacity(snap_ids.len());
+    let mut to_refresh = HashSet::new();
+
+    for snap_id in snap_ids {
+        match known.get(snap_id) {
+            Some(snap) if snap.last_refreshed >= stale_before => {
+                names.insert(snap_id.clone(), snap.name.clone());
+            }
+            _ => {
+                to_refresh.insert(snap_id.as_str());
+            }
+        }
+    }
+
+    let requests: Vec<_> = to_refresh
+        .iter()
+        .map(|snap_id| get_snap_declaration(snap_id, &ctx.snapcraft))
+        .collect();
+    let refreshed: Vec<_> = stream::iter(requests)
+        .buffered(ctx.config.snap_metadata_refresh_concurrency.max(1))
+        .collect()
+        .await;
+
+    let mut declarations = Vec::with_capacity(refreshed.len());
+    for (snap_id, res) in to_refresh.into_iter().zip(refreshed) {
+        match (res, known.get(snap_id)) {
+            (Ok(decl), _) => declarations.push((snap_id, decl)),
+
+            (Err(e), Some(stale)) => {
+                warn!(%snap_id, "unable to refresh snap metadata, using stale data: {e}");
+                names.insert(snap_id.to_string(), stale.name.clone());
+            }
+
+            (Err(e), None) => {
+                warn!(%snap_id, "unable to fetch snap metadata: {e}");
+            }
+        }
+    }
+
+    if !declarations.is_empty() {
+        let conn = conn!();
+        for (snap_id, decl) in declarations {
+            Snap::save(snap_id, &decl.snap_name, decl.publisher_id.as_deref(), conn).await?;
+            names.insert(snap_id.to_string(), decl.snap_name);
+        }
+    }
+
+    Ok(names)
+}
+
+/// Look up the publisher that owns a snap, returning `None` if snapcraft.io doesn't know the
+/// snap or its publisher.
+///
+/// Ownership decides who may act on behalf of a snap so, unlike [`get_snap_names`], this always
+/// asks snapcraft.io rather than trusting stored metadata. The stored metadata is refreshed with
+/// the result.
+pub async fn get_snap_publisher(snap_id: &str, ctx: &Context) -> Result<Option<String>, Error> {
+    match get_snap_declaration(snap_id, &ctx.snapcraft).await {
+        Ok(decl) => {
+            Snap::save(
+                snap_id,
+                &decl.snap_name,
+                decl.publisher_id.as_deref(),
+                conn!(),
+            )
+            .await?;
+            Ok(decl.publisher_id)
+        }
+
+        Err(Error::SnapcraftIo(e)) if e.status() == Some(StatusCode::NOT_FOUND) => Ok(None),
+
+        Err(e) => Err(e),
+    }
+}
+
+/// The subset of the snap declaration assertion that we make use of.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SnapDeclaration {
+    pub snap_name: String,
+    #[serde(default)]
+    pub publisher_id: Option<String>,
+}
+
+/// Pull the snap declaration for a given snap_id from the snapcraft.io rest API
+pub(crate) async fn get_snap_declaration(
+    snap_id: &str,
+    client: &SnapcraftClient,
+) -> Result<SnapDeclaration, Error> {
+    let AssertionsResp { headers } = client
+        .get_json(&format!("assertions/snap-declaration/16/{snap_id}"), &[])
+        .await?;
+
+    return Ok(headers);
+
+    // serde structs
+    //
+    #[derive(Debug, Deserialize)]
+    struct AssertionsResp {
+        headers: SnapDeclaration,
+    }
+}
@@ -0,0 +1,125 @@
+pub mod common;
+
+use common::{status_code, Category, TestHelper};
+use tonic::Code;
+
+#[tokio::test]
+async fn admin_rpcs_require_an_admin_token() -> anyhow::Result<()> {
+    let t = TestHelper::new();
+    let token = t.authenticate(t.random_sha_256()).await?;
+
+    let res = t.get_vote_stats(&t.random_id(), &token).await;
+    assert_eq!(status_code(res), Code::PermissionDenied);
+
+    let res = t.get_vote_stats(&t.random_id(), "not-a-token").await;
+    assert_eq!(status_code(res), Code::Unauthenticated);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn vote_stats_are_reported_per_revision() -> anyhow::Result<()> {
+    let t = TestHelper::new();
+    let admin = t.admin_token();
+    let snap_id = t.test_snap_with_initial_votes(1, 3, 2, &[]).await?;
+    t.generate_votes(&snap_id, 2, true, 1).await?;
+
+    let stats = t.get_vote_stats(&snap_id, &admin).await?;
+
+    assert_eq!(stats.total_votes, 6);
+    assert_eq!(stats.positive_votes, 4);
+    assert_eq!(stats.unique_voters, 6);
+    assert_eq!(stats.flagged_votes, 0);
+    assert!(stats.first_vote.is_some() && stats.last_vote.is_some());
+    let revisions: Vec<_> = stats
+        .revisions
+        .iter()
+        .map(|r| (r.snap_revision, r.total_votes, r.positive_votes))
+        .collect();
+    assert_eq!(revisions, vec![(2, 1, 1), (1, 5, 3)]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn votes_can_be_removed_for_a_snap() -> anyhow::Result<()> {
+    let t = TestHelper::new();
+    let admin = t.admin_token();
+    let snap_id = t.test_snap_with_initial_votes(1, 3, 0, &[]).await?;
+    let client_hash = t.random_sha_256();
+    let token = t.authenticate(client_hash.clone()).await?;
+    t.vote(&snap_id, 1, false, &token).await?;
+
+    assert_eq!(
+        t.remove_votes(&snap_id, Some(client_hash), &admin).await?,
+        1
+    );
+    assert_eq!(t.get_vote_stats(&snap_id, &admin).await?.total_votes, 3);
+
+    assert_eq!(t.remove_votes(&snap_id, None, &admin).await?, 3);
+    assert_eq!(t.get_vote_stats(&snap_id, &admin).await?.total_votes, 0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn banned_clients_are_locked_out_until_unbanned() -> anyhow::Result<()> {
+    let t = TestHelper::new();
+    let admin = t.admin_token();
+    let snap_id = t.random_id();
+    let client_hash = t.random_sha_256();
+    let (token, refresh_token) = t.authenticate_with_refresh(client_hash.clone()).await?;
+    t.vote(&snap_id, 1, true, &token).await?;
+
+    assert_eq!(t.ban_client(&client_hash, true, &admin).await?, 1);
+    assert_eq!(t.get_vote_stats(&snap_id, &admin).await?.total_votes, 0);
+
+    let res = t.vote(&snap_id, 1, true, &token).await;
+    assert_eq!(status_code(res), Code::Unauthenticated);
+    let res = t.refresh_token(refresh_token).await;
+    assert_eq!(status_code(res), Code::PermissionDenied);
+    let res = t.authenticate(client_hash.clone()).await;
+    assert_eq!(status_code(res), Code::PermissionDenied);
+
+    assert!(t.unban_client(&client_hash, &admin).await?);
+    assert!(!t.unban_client(&client_hash, &admin).await?);
+    t.wait_out_revocations().await;
+    let new_token = t.authenticate(client_hash).await?;
+    t.vote(&snap_id, 1, true, &new_token).await?;
+
+    // Tokens issued before the ban stay revoked
+    let res = t.vote(&snap_id, 1, true, &token).await;
+    assert_eq!(status_code(res), Code::Unauthenticated);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn categories_can_be_refreshed() -> anyhow::Result<()> {
+    let t = TestHelper::new();
+    let admin = t.admin_token();
+    let snap_id = t
+        .test_snap_with_initial_votes(1, 1, 0, &[Category::Games, Category::Social])
+        .await?;
+
+    let mut categories = t.refresh_categories(&snap_id, &admin).await?;
+    categories.sort_by_key(|c| *c as i32);
+
+    assert_eq!(categories, vec![Category::Games, Category::Social]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn bursts_of_votes_from_new_users_are_flagged() -> anyhow::Result<()> {
+    let t = TestHelper::new();
+    let admin = t.admin_token();
+    let snap_id = t.test_snap_with_initial_votes(1, 25, 0, &[]).await?;
+
+    let stats = t.get_vote_stats(&snap_id, &admin).await?;
+
+    assert_eq!(stats.total_votes, 25);
+    assert_eq!(stats.flagged_votes, 25);
+
+    Ok(())
+}
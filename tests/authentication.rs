@@ -24,9 +24,98 @@ async fn valid_client_hashes_can_authenticate_multiple_times() -> anyhow::Result
     let token1 = t.authenticate(client_hash.clone()).await?;
     let token2 = t.authenticate(client_hash.clone()).await?;
 
-    assert_eq!(token1, token2);
+    assert_ne!(token1, token2, "each token should be unique");
     t.assert_valid_jwt(&token1);
     t.assert_valid_jwt(&token2);
 
     Ok(())
 }
+
+#[tokio::test]
+async fn refresh_tokens_can_be_exchanged_for_new_tokens() -> anyhow::Result<()> {
+    let t = TestHelper::new();
+    let snap_id = t.random_id();
+
+    let (_, refresh_token) = t.authenticate_with_refresh(t.random_sha_256()).await?;
+    let (token, new_refresh_token) = t.refresh_token(refresh_token).await?;
+
+    t.assert_valid_jwt(&token);
+    t.assert_valid_jwt(&new_refresh_token);
+    t.get_rating(&snap_id, &token).await?;
+
+    // The new refresh token can itself be refreshed
+    t.refresh_token(new_refresh_token).await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn refresh_tokens_can_only_be_used_once() -> anyhow::Result<()> {
+    let t = TestHelper::new();
+
+    let (_, refresh_token) = t.authenticate_with_refresh(t.random_sha_256()).await?;
+    let (_, new_refresh_token) = t.refresh_token(refresh_token.clone()).await?;
+
+    let err = t.refresh_token(refresh_token).await.unwrap_err();
+    let status = err.downcast_ref::<tonic::Status>().expect("a grpc status");
+    assert_eq!(status.code(), tonic::Code::Unauthenticated, "{status:?}");
+
+    // Reusing the old token doesn't affect the one it was exchanged for
+    t.refresh_token(new_refresh_token).await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn access_and_refresh_tokens_are_not_interchangeable() -> anyhow::Result<()> {
+    let t = TestHelper::new();
+    let snap_id = t.random_id();
+
+    let (token, refresh_token) = t.authenticate_with_refresh(t.random_sha_256()).await?;
+
+    let res = t.get_rating(&snap_id, &refresh_token).await;
+    assert!(res.is_err(), "refresh token used for access: {res:?}");
+
+    let res = t.refresh_token(token).await;
+    assert!(res.is_err(), "access token used for refresh: {res:?}");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn refresh_tokens_for_deleted_users_are_rejected() -> anyhow::Result<()> {
+    let t = TestHelper::new();
+
+    let (token, refresh_token) = t.authenticate_with_refresh(t.random_sha_256()).await?;
+    t.delete_user(&token).await?;
+
+    let res = t.refresh_token(refresh_token).await;
+    assert!(res.is_err(), "{res:?}");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn access_tokens_for_deleted_users_are_revoked() -> anyhow::Result<()> {
+    let t = TestHelper::new();
+    let client_hash = t.random_sha_256();
+    let snap_id = t.random_id();
+
+    let token = t.authenticate(client_hash.clone()).await?;
+    t.delete_user(&token).await?;
+
+    let err = t.vote(&snap_id, 1, true, &token).await.unwrap_err();
+    let status = err.downcast_ref::<tonic::Status>().expect("a grpc status");
+    assert_eq!(status.code(), tonic::Code::Unauthenticated, "{status:?}");
+
+    // Authenticating again restores access, but only through newly issued tokens
+    t.wait_out_revocations().await;
+    let new_token = t.authenticate(client_hash).await?;
+    t.vote(&snap_id, 1, true, &new_token).await?;
+
+    let err = t.vote(&snap_id, 1, true, &token).await.unwrap_err();
+    let status = err.downcast_ref::<tonic::Status>().expect("a grpc status");
+    assert_eq!(status.code(), tonic::Code::Unauthenticated, "{status:?}");
+
+    Ok(())
+}
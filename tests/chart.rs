@@ -4,7 +4,7 @@
 //       making use of any of the Categories that the tests in this file rely on.
 pub mod common;
 
-use common::{Category, TestHelper};
+use common::{Category, ChartType, GetChartRequest, TestHelper, Timeframe};
 use rand::{thread_rng, Rng};
 use simple_test_case::test_case;
 
@@ -72,6 +72,153 @@ async fn category_chart_returns_expected_order(
     Ok(())
 }
 
+// !! This test expects to be the only one making use of the "Education" category
+#[tokio::test]
+async fn category_chart_can_be_paged_through() -> anyhow::Result<()> {
+    let t = TestHelper::new();
+    let mut ids = Vec::with_capacity(25);
+
+    // More snaps than fit in a single default sized page
+    for i in 0..25 {
+        let id = t
+            .test_snap_with_initial_votes(1, 25 + i, 0, &[Category::Education])
+            .await?;
+        ids.push(id);
+    }
+    ids.reverse();
+
+    let user_token = t.authenticate(t.random_sha_256()).await?;
+    let mut seen = Vec::with_capacity(ids.len());
+    let mut page_token = String::new();
+
+    loop {
+        let (data, next) = t
+            .get_chart_page(Some(Category::Education), 10, page_token, &user_token)
+            .await?;
+        assert!(data.len() <= 10, "page too large: {}", data.len());
+        seen.extend(data.into_iter().map(|c| c.rating.unwrap().snap_id));
+
+        if next.is_empty() {
+            break;
+        }
+        page_token = next;
+    }
+
+    assert_eq!(seen, ids);
+
+    Ok(())
+}
+
+// !! This test expects to be the only one making use of the "Finance" category
+#[test_case(Timeframe::Unspecified; "unspecified")]
+#[test_case(Timeframe::Day; "day")]
+#[test_case(Timeframe::Week; "week")]
+#[test_case(Timeframe::Month; "month")]
+#[test_case(Timeframe::Quarter; "quarter")]
+#[test_case(Timeframe::Year; "year")]
+#[test_case(Timeframe::AllTime; "all time")]
+#[tokio::test]
+async fn chart_filters_can_be_combined(timeframe: Timeframe) -> anyhow::Result<()> {
+    let t = TestHelper::new();
+
+    // A snap which matches every filter and one which only matches timeframe and category
+    let popular = t
+        .test_snap_with_initial_votes(2, 20, 10, &[Category::Finance])
+        .await?;
+    let unpopular = t
+        .test_snap_with_initial_votes(1, 2, 1, &[Category::Finance])
+        .await?;
+
+    let user_token = t.authenticate(t.random_sha_256()).await?;
+
+    for category in [None, Some(Category::Finance)] {
+        for min_votes in [None, Some(10)] {
+            for revisions in [None, Some((2, 3))] {
+                let request = GetChartRequest {
+                    timeframe: timeframe.into(),
+                    category: category.map(|c| c as i32),
+                    min_votes,
+                    min_revision: revisions.map(|(min, _)| min),
+                    max_revision: revisions.map(|(_, max)| max),
+                    ..Default::default()
+                };
+                let desc = format!("{request:?}");
+
+                let ids: Vec<String> = t
+                    .get_full_chart(request, &user_token)
+                    .await?
+                    .into_iter()
+                    .map(|c| c.rating.unwrap().snap_id)
+                    .collect();
+
+                assert!(ids.contains(&popular), "popular snap missing: {desc}");
+                assert_eq!(
+                    ids.contains(&unpopular),
+                    min_votes.is_none() && revisions.is_none(),
+                    "unpopular snap: {desc}"
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// !! This test expects to be the only one making use of the "HealthAndFitness" category
+#[tokio::test]
+async fn trending_chart_ranks_by_new_positive_votes() -> anyhow::Result<()> {
+    let t = TestHelper::new();
+
+    // All votes are cast now so they fall within the current window, the rating of the snap
+    // itself should not matter
+    let steady = t
+        .test_snap_with_initial_votes(1, 5, 0, &[Category::HealthAndFitness])
+        .await?;
+    let rising = t
+        .test_snap_with_initial_votes(1, 30, 25, &[Category::HealthAndFitness])
+        .await?;
+
+    let user_token = t.authenticate(t.random_sha_256()).await?;
+    let ids: Vec<String> = t
+        .get_full_chart(
+            GetChartRequest {
+                category: Some(Category::HealthAndFitness as i32),
+                chart_type: ChartType::Trending.into(),
+                ..Default::default()
+            },
+            &user_token,
+        )
+        .await?
+        .into_iter()
+        .map(|c| c.rating.unwrap().snap_id)
+        .collect();
+
+    assert_eq!(ids, vec![rising, steady]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn trending_chart_requires_a_bounded_timeframe() -> anyhow::Result<()> {
+    let t = TestHelper::new();
+    let user_token = t.authenticate(t.random_sha_256()).await?;
+
+    let res = t
+        .get_full_chart(
+            GetChartRequest {
+                timeframe: Timeframe::AllTime.into(),
+                chart_type: ChartType::Trending.into(),
+                ..Default::default()
+            },
+            &user_token,
+        )
+        .await;
+
+    assert!(res.is_err(), "{res:?}");
+
+    Ok(())
+}
+
 fn random_votes(min_vote: usize, max_vote: usize, min_up: usize, max_up: usize) -> (u64, u64) {
     let mut rng = thread_rng();
     let upvotes = rng.gen_range(min_up..max_up);
@@ -1,3 +1,11 @@
 DELETE FROM snap_categories;
 DELETE FROM users;
 DELETE FROM votes;
+DELETE FROM snaps;
+DELETE FROM token_revocations;
+DELETE FROM banned_clients;
+DELETE FROM review_replies;
+DELETE FROM content_reports;
+DELETE FROM reviews;
+DELETE FROM moderated_votes;
+DELETE FROM used_refresh_tokens;
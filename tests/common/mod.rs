@@ -2,12 +2,24 @@ use anyhow::anyhow;
 use futures::future::join_all;
 use rand::{distributions::Alphanumeric, Rng};
 use ratings::{
-    jwt::JwtVerifier,
+    jwt::{JwtEncoder, JwtVerifier},
     proto::{
-        app::{app_client::AppClient, GetRatingRequest},
-        chart::{chart_client::ChartClient, ChartData, GetChartRequest, Timeframe},
+        admin::{
+            admin_client::AdminClient, BanClientRequest, GetVoteStatsRequest, GetVoteStatsResponse,
+            ListReportsRequest, RefreshCategoriesRequest, RemoveVotesRequest, Report,
+            ResolveReportRequest, UnbanClientRequest,
+        },
+        app::{
+            app_client::AppClient, GetFeedbackBreakdownRequest, GetFeedbackBreakdownResponse,
+            GetRatingByRevisionRequest, GetRatingRequest, GetRatingsRequest, ListReviewsRequest,
+            ListReviewsResponse, RevisionRating,
+        },
+        chart::{chart_client::ChartClient, ChartData},
+        publisher::{publisher_client::PublisherClient, ReplyToReviewRequest},
         user::{
-            user_client::UserClient, AuthenticateRequest, GetSnapVotesRequest, Vote, VoteRequest,
+            user_client::UserClient, AuthenticateRequest, DeleteReviewRequest, GetSnapVotesRequest,
+            RefreshTokenRequest, ReportContentRequest, RetractVoteRequest, SubmitReviewRequest,
+            Vote, VoteRequest,
         },
     },
     ratings::Rating,
@@ -20,11 +32,25 @@ use std::fmt::Write;
 use tonic::{
     metadata::MetadataValue,
     transport::{Channel, Endpoint},
-    Request,
+    Code, Request, Status,
+};
+use tonic_health::pb::{
+    health_check_response::ServingStatus, health_client::HealthClient, HealthCheckRequest,
+};
+use tonic_reflection::pb::v1::{
+    server_reflection_client::ServerReflectionClient, server_reflection_request::MessageRequest,
+    server_reflection_response::MessageResponse, ServerReflectionRequest,
 };
 
 // re-export to simplify setting up test data in the test files
-pub use ratings::db::Category;
+pub use ratings::{
+    db::Category,
+    proto::{
+        admin::ReportState,
+        chart::{ChartType, GetChartRequest, Timeframe},
+        common::{ReportReason, VoteReason},
+    },
+};
 
 // NOTE: these are set by the 'tests' Makefile target
 const MOCK_ADMIN_URL: Option<&str> = option_env!("MOCK_ADMIN_URL");
@@ -42,6 +68,14 @@ macro_rules! client {
     };
 }
 
+/// The gRPC status code of a request that is expected to have failed
+pub fn status_code(res: anyhow::Result<impl std::fmt::Debug>) -> Code {
+    let err = res.unwrap_err();
+    err.downcast_ref::<Status>()
+        .unwrap_or_else(|| panic!("expected a grpc status: {err:?}"))
+        .code()
+}
+
 fn rnd_string(len: usize) -> String {
     let rng = rand::thread_rng();
     rng.sample_iter(&Alphanumeric)
@@ -70,15 +104,11 @@ impl TestHelper {
         }
     }
 
-    pub fn assert_valid_jwt(&self, value: &str) {
+    fn jwt_secret(&self) -> SecretString {
         dotenvy::dotenv().ok();
         let JwtConfig { jwt_secret } = envy::prefixed("APP_").from_env::<JwtConfig>().unwrap();
-        let verifier = JwtVerifier::from_secret(&jwt_secret).expect("unable to init JwtVerifier");
 
-        assert!(
-            verifier.decode(value).is_ok(),
-            "value should be a valid jwt"
-        );
+        return jwt_secret;
 
         // serde structs
         #[derive(Deserialize)]
@@ -87,6 +117,50 @@ impl TestHelper {
         }
     }
 
+    pub fn assert_valid_jwt(&self, value: &str) {
+        let verifier =
+            JwtVerifier::from_secret(&self.jwt_secret()).expect("unable to init JwtVerifier");
+
+        assert!(
+            verifier.decode(value).is_ok(),
+            "value should be a valid jwt"
+        );
+    }
+
+    /// Wait until newly issued tokens are no longer covered by revocations that have already been
+    /// made: tokens only record the second they were issued in.
+    pub async fn wait_out_revocations(&self) {
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+
+    /// Mint an admin token in the same way as the `mint-admin-token` subcommand
+    pub fn admin_token(&self) -> String {
+        JwtEncoder::from_secret(&self.jwt_secret())
+            .expect("unable to init JwtEncoder")
+            .encode_admin("integration-tests".to_string())
+            .expect("unable to encode admin token")
+    }
+
+    /// Mint a publisher token in the same way as the `mint-publisher-token` subcommand
+    pub fn publisher_token(&self, publisher_id: &str) -> String {
+        JwtEncoder::from_secret(&self.jwt_secret())
+            .expect("unable to init JwtEncoder")
+            .encode_publisher(publisher_id.to_string())
+            .expect("unable to encode publisher token")
+    }
+
+    /// Register a snap owned by the given publisher with the mock snapcraft.io server
+    pub async fn test_snap_with_publisher(&self, publisher_id: &str) -> anyhow::Result<String> {
+        let snap_id = self.random_id();
+        self.client
+            .post(format!("{}/{snap_id}", self.mock_admin_url))
+            .query(&[("publisher_id", publisher_id)])
+            .send()
+            .await?;
+
+        Ok(snap_id)
+    }
+
     /// NOTE: total needs to be above 25 in order to generate a rating
     pub async fn test_snap_with_initial_votes(
         &self,
@@ -200,6 +274,32 @@ impl TestHelper {
             .ok_or(anyhow!("no rating for {id}"))
     }
 
+    pub async fn get_ratings(&self, ids: &[&str], token: &str) -> anyhow::Result<Vec<Rating>> {
+        let resp = client!(AppClient, self.channel().await, token)
+            .get_ratings(GetRatingsRequest {
+                snap_ids: ids.iter().map(|id| id.to_string()).collect(),
+            })
+            .await?
+            .into_inner();
+
+        Ok(resp.ratings.into_iter().map(Into::into).collect())
+    }
+
+    pub async fn get_rating_by_revision(
+        &self,
+        id: &str,
+        token: &str,
+    ) -> anyhow::Result<Vec<RevisionRating>> {
+        let resp = client!(AppClient, self.channel().await, token)
+            .get_rating_by_revision(GetRatingByRevisionRequest {
+                snap_id: id.to_string(),
+            })
+            .await?
+            .into_inner();
+
+        Ok(resp.revisions)
+    }
+
     pub async fn get_chart(
         &self,
         category: Option<Category>,
@@ -209,6 +309,7 @@ impl TestHelper {
             .get_chart(GetChartRequest {
                 timeframe: Timeframe::Unspecified.into(),
                 category: category.map(|v| v as i32),
+                ..Default::default()
             })
             .await?
             .into_inner();
@@ -216,6 +317,52 @@ impl TestHelper {
         Ok(resp.ordered_chart_data)
     }
 
+    /// Fetch a single page of a chart, returning the data and the token for the next page
+    pub async fn get_chart_page(
+        &self,
+        category: Option<Category>,
+        page_size: u32,
+        page_token: String,
+        token: &str,
+    ) -> anyhow::Result<(Vec<ChartData>, String)> {
+        let resp = client!(ChartClient, self.channel().await, token)
+            .get_chart(GetChartRequest {
+                timeframe: Timeframe::Unspecified.into(),
+                category: category.map(|v| v as i32),
+                page_size: Some(page_size),
+                page_token,
+                ..Default::default()
+            })
+            .await?
+            .into_inner();
+
+        Ok((resp.ordered_chart_data, resp.next_page_token))
+    }
+
+    /// Fetch every entry in the chart described by the given request, following page tokens
+    /// until the end of the chart is reached
+    pub async fn get_full_chart(
+        &self,
+        mut request: GetChartRequest,
+        token: &str,
+    ) -> anyhow::Result<Vec<ChartData>> {
+        let mut data = Vec::new();
+        request.page_size = Some(100);
+
+        loop {
+            let resp = client!(ChartClient, self.channel().await, token)
+                .get_chart(request.clone())
+                .await?
+                .into_inner();
+            data.extend(resp.ordered_chart_data);
+
+            if resp.next_page_token.is_empty() {
+                return Ok(data);
+            }
+            request.page_token = resp.next_page_token;
+        }
+    }
+
     pub async fn vote(
         &self,
         snap_id: &str,
@@ -228,12 +375,65 @@ impl TestHelper {
                 snap_id: snap_id.to_string(),
                 snap_revision,
                 vote_up,
+                ..Default::default()
             })
             .await?;
 
         Ok(())
     }
 
+    pub async fn downvote_with_reasons(
+        &self,
+        snap_id: &str,
+        snap_revision: i32,
+        reasons: &[VoteReason],
+        token: &str,
+    ) -> anyhow::Result<()> {
+        client!(UserClient, self.channel().await, token)
+            .vote(VoteRequest {
+                snap_id: snap_id.to_string(),
+                snap_revision,
+                vote_up: false,
+                reasons: reasons.iter().map(|&r| r.into()).collect(),
+            })
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_feedback_breakdown(
+        &self,
+        snap_id: &str,
+        token: &str,
+    ) -> anyhow::Result<GetFeedbackBreakdownResponse> {
+        let resp = client!(AppClient, self.channel().await, token)
+            .get_feedback_breakdown(GetFeedbackBreakdownRequest {
+                snap_id: snap_id.to_string(),
+            })
+            .await?
+            .into_inner();
+
+        Ok(resp)
+    }
+
+    /// Retract a previous vote, returning whether there was a vote to retract
+    pub async fn retract_vote(
+        &self,
+        snap_id: &str,
+        snap_revision: i32,
+        token: &str,
+    ) -> anyhow::Result<bool> {
+        let resp = client!(UserClient, self.channel().await, token)
+            .retract_vote(RetractVoteRequest {
+                snap_id: snap_id.to_string(),
+                snap_revision,
+            })
+            .await?
+            .into_inner();
+
+        Ok(resp.retracted)
+    }
+
     pub async fn get_snap_votes(
         &self,
         token: &str,
@@ -247,13 +447,267 @@ impl TestHelper {
         Ok(resp.votes)
     }
 
+    /// Check the health of a service, without authenticating
+    pub async fn submit_review(
+        &self,
+        snap_id: &str,
+        snap_revision: i32,
+        body: &str,
+        token: &str,
+    ) -> anyhow::Result<()> {
+        client!(UserClient, self.channel().await, token)
+            .submit_review(SubmitReviewRequest {
+                snap_id: snap_id.to_string(),
+                snap_revision,
+                body: body.to_string(),
+            })
+            .await?;
+
+        Ok(())
+    }
+
+    /// Delete a review, returning whether there was a review to delete
+    pub async fn delete_review(&self, snap_id: &str, token: &str) -> anyhow::Result<bool> {
+        let resp = client!(UserClient, self.channel().await, token)
+            .delete_review(DeleteReviewRequest {
+                snap_id: snap_id.to_string(),
+            })
+            .await?
+            .into_inner();
+
+        Ok(resp.deleted)
+    }
+
+    /// List reviews without authenticating, as the endpoint is public
+    pub async fn list_reviews(
+        &self,
+        request: ListReviewsRequest,
+    ) -> anyhow::Result<ListReviewsResponse> {
+        let resp = AppClient::connect(self.server_url.clone())
+            .await?
+            .list_reviews(request)
+            .await?
+            .into_inner();
+
+        Ok(resp)
+    }
+
+    pub async fn health_check(&self, service: &str) -> anyhow::Result<ServingStatus> {
+        let resp = HealthClient::new(self.channel().await)
+            .check(HealthCheckRequest {
+                service: service.to_string(),
+            })
+            .await?
+            .into_inner();
+
+        Ok(resp.status())
+    }
+
+    /// List the services exposed via server reflection, without authenticating
+    pub async fn list_services(&self) -> anyhow::Result<Vec<String>> {
+        let req = ServerReflectionRequest {
+            host: String::new(),
+            message_request: Some(MessageRequest::ListServices(String::new())),
+        };
+
+        let mut stream = ServerReflectionClient::new(self.channel().await)
+            .server_reflection_info(futures::stream::iter([req]))
+            .await?
+            .into_inner();
+
+        match stream.message().await?.and_then(|r| r.message_response) {
+            Some(MessageResponse::ListServicesResponse(resp)) => {
+                Ok(resp.service.into_iter().map(|s| s.name).collect())
+            }
+            other => Err(anyhow!("unexpected reflection response: {other:?}")),
+        }
+    }
+
     pub async fn authenticate(&self, id: String) -> anyhow::Result<String> {
+        let (token, _) = self.authenticate_with_refresh(id).await?;
+
+        Ok(token)
+    }
+
+    /// Authenticate, returning both the access token and the refresh token
+    pub async fn authenticate_with_refresh(&self, id: String) -> anyhow::Result<(String, String)> {
         let resp = UserClient::connect(self.server_url.clone())
             .await?
             .authenticate(AuthenticateRequest { id })
             .await?
             .into_inner();
 
-        Ok(resp.token)
+        Ok((resp.token, resp.refresh_token))
+    }
+
+    /// Delete the authenticated user along with all of their votes
+    pub async fn delete_user(&self, token: &str) -> anyhow::Result<()> {
+        client!(UserClient, self.channel().await, token)
+            .delete(())
+            .await?;
+
+        Ok(())
+    }
+
+    /// Exchange a refresh token for a new access token and refresh token
+    pub async fn refresh_token(&self, refresh_token: String) -> anyhow::Result<(String, String)> {
+        let resp = UserClient::connect(self.server_url.clone())
+            .await?
+            .refresh_token(RefreshTokenRequest { refresh_token })
+            .await?
+            .into_inner();
+
+        Ok((resp.token, resp.refresh_token))
+    }
+
+    /// Remove the votes for a snap, returning the number removed
+    pub async fn remove_votes(
+        &self,
+        snap_id: &str,
+        client_hash: Option<String>,
+        token: &str,
+    ) -> anyhow::Result<u64> {
+        let resp = client!(AdminClient, self.channel().await, token)
+            .remove_votes(RemoveVotesRequest {
+                snap_id: snap_id.to_string(),
+                client_hash,
+            })
+            .await?
+            .into_inner();
+
+        Ok(resp.removed_votes)
+    }
+
+    /// Ban a client, returning the number of their votes that were removed
+    pub async fn ban_client(
+        &self,
+        client_hash: &str,
+        remove_votes: bool,
+        token: &str,
+    ) -> anyhow::Result<u64> {
+        let resp = client!(AdminClient, self.channel().await, token)
+            .ban_client(BanClientRequest {
+                client_hash: client_hash.to_string(),
+                reason: "integration test".to_string(),
+                remove_votes,
+            })
+            .await?
+            .into_inner();
+
+        Ok(resp.removed_votes)
+    }
+
+    /// Lift the ban on a client, returning whether they were banned
+    pub async fn unban_client(&self, client_hash: &str, token: &str) -> anyhow::Result<bool> {
+        let resp = client!(AdminClient, self.channel().await, token)
+            .unban_client(UnbanClientRequest {
+                client_hash: client_hash.to_string(),
+            })
+            .await?
+            .into_inner();
+
+        Ok(resp.unbanned)
+    }
+
+    pub async fn refresh_categories(
+        &self,
+        snap_id: &str,
+        token: &str,
+    ) -> anyhow::Result<Vec<Category>> {
+        let resp = client!(AdminClient, self.channel().await, token)
+            .refresh_categories(RefreshCategoriesRequest {
+                snap_id: snap_id.to_string(),
+            })
+            .await?
+            .into_inner();
+
+        Ok(resp
+            .categories
+            .into_iter()
+            .map(|c| Category::from_repr(c).expect("a valid category"))
+            .collect())
+    }
+
+    pub async fn get_vote_stats(
+        &self,
+        snap_id: &str,
+        token: &str,
+    ) -> anyhow::Result<GetVoteStatsResponse> {
+        let resp = client!(AdminClient, self.channel().await, token)
+            .get_vote_stats(GetVoteStatsRequest {
+                snap_id: snap_id.to_string(),
+            })
+            .await?
+            .into_inner();
+
+        Ok(resp)
+    }
+
+    pub async fn report_content(
+        &self,
+        review_id: i32,
+        reason: ReportReason,
+        token: &str,
+    ) -> anyhow::Result<()> {
+        client!(UserClient, self.channel().await, token)
+            .report_content(ReportContentRequest {
+                review_id,
+                reason: reason.into(),
+                details: "integration test".to_string(),
+            })
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn list_reports(
+        &self,
+        state: Option<ReportState>,
+        token: &str,
+    ) -> anyhow::Result<Vec<Report>> {
+        let resp = client!(AdminClient, self.channel().await, token)
+            .list_reports(ListReportsRequest {
+                state: state.map(Into::into),
+                page_size: Some(100),
+                ..Default::default()
+            })
+            .await?
+            .into_inner();
+
+        Ok(resp.reports)
+    }
+
+    /// Resolve a report, returning the number of reports resolved
+    pub async fn resolve_report(
+        &self,
+        report_id: i32,
+        resolution: ReportState,
+        token: &str,
+    ) -> anyhow::Result<u64> {
+        let resp = client!(AdminClient, self.channel().await, token)
+            .resolve_report(ResolveReportRequest {
+                report_id,
+                resolution: resolution.into(),
+            })
+            .await?
+            .into_inner();
+
+        Ok(resp.resolved_reports)
+    }
+
+    pub async fn reply_to_review(
+        &self,
+        review_id: i32,
+        body: &str,
+        token: &str,
+    ) -> anyhow::Result<()> {
+        client!(PublisherClient, self.channel().await, token)
+            .reply_to_review(ReplyToReviewRequest {
+                review_id,
+                body: body.to_string(),
+            })
+            .await?;
+
+        Ok(())
     }
 }
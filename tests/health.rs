@@ -0,0 +1,42 @@
+pub mod common;
+
+use common::TestHelper;
+use simple_test_case::test_case;
+use tonic_health::pb::health_check_response::ServingStatus;
+
+#[test_case(""; "server")]
+#[test_case("ratings.features.admin.Admin"; "admin")]
+#[test_case("ratings.features.app.App"; "app")]
+#[test_case("ratings.features.chart.Chart"; "chart")]
+#[test_case("ratings.features.publisher.Publisher"; "publisher")]
+#[test_case("ratings.features.user.User"; "user")]
+#[tokio::test]
+async fn services_report_as_serving(service: &str) -> anyhow::Result<()> {
+    let t = TestHelper::new();
+
+    assert_eq!(t.health_check(service).await?, ServingStatus::Serving);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn services_are_listed_via_reflection() -> anyhow::Result<()> {
+    let t = TestHelper::new();
+    let services = t.list_services().await?;
+
+    for service in [
+        "ratings.features.admin.Admin",
+        "ratings.features.app.App",
+        "ratings.features.chart.Chart",
+        "ratings.features.publisher.Publisher",
+        "ratings.features.user.User",
+        "grpc.health.v1.Health",
+    ] {
+        assert!(
+            services.iter().any(|s| s == service),
+            "{service} missing from {services:?}"
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,178 @@
+pub mod common;
+
+use common::{status_code, ReportReason, ReportState, TestHelper};
+use ratings::proto::app::ListReviewsRequest;
+use tonic::Code;
+
+/// Write a review of a snap along with a vote on the reviewed revision, returning the review id
+async fn reviewed_snap(t: &TestHelper, vote_up: bool) -> anyhow::Result<(String, i32)> {
+    let snap_id = t.test_snap_with_initial_votes(1, 3, 0, &[]).await?;
+    let token = t.authenticate(t.random_sha_256()).await?;
+    t.vote(&snap_id, 1, vote_up, &token).await?;
+    t.submit_review(&snap_id, 1, "this snap is rubbish", &token)
+        .await?;
+
+    let reviews = t
+        .list_reviews(ListReviewsRequest {
+            snap_id: snap_id.clone(),
+            ..Default::default()
+        })
+        .await?
+        .reviews;
+
+    Ok((snap_id, reviews[0].id))
+}
+
+async fn open_reports_for(t: &TestHelper, review_id: i32) -> anyhow::Result<Vec<i32>> {
+    let reports = t
+        .list_reports(Some(ReportState::Open), &t.admin_token())
+        .await?;
+
+    Ok(reports
+        .into_iter()
+        .filter(|r| r.review_id == Some(review_id))
+        .map(|r| r.id)
+        .collect())
+}
+
+#[tokio::test]
+async fn actioned_reviews_are_hidden_along_with_their_vote() -> anyhow::Result<()> {
+    let t = TestHelper::new();
+    let admin = t.admin_token();
+    let (snap_id, review_id) = reviewed_snap(&t, false).await?;
+    let token = t.authenticate(t.random_sha_256()).await?;
+    assert_eq!(t.get_rating(&snap_id, &token).await?.total_votes, 4);
+
+    for reason in [ReportReason::Abuse, ReportReason::Spam] {
+        let reporter = t.authenticate(t.random_sha_256()).await?;
+        t.report_content(review_id, reason, &reporter).await?;
+    }
+    let reports = open_reports_for(&t, review_id).await?;
+    assert_eq!(reports.len(), 2);
+
+    assert_eq!(
+        t.resolve_report(reports[0], ReportState::Actioned, &admin)
+            .await?,
+        2
+    );
+    assert!(open_reports_for(&t, review_id).await?.is_empty());
+
+    let reviews = t
+        .list_reviews(ListReviewsRequest {
+            snap_id: snap_id.clone(),
+            ..Default::default()
+        })
+        .await?
+        .reviews;
+    assert!(reviews.is_empty());
+    assert_eq!(t.get_rating(&snap_id, &token).await?.total_votes, 3);
+
+    let res = t
+        .report_content(review_id, ReportReason::Other, &token)
+        .await;
+    assert_eq!(status_code(res), Code::NotFound);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn actioned_reviews_and_votes_cant_be_brought_back() -> anyhow::Result<()> {
+    let t = TestHelper::new();
+    let admin = t.admin_token();
+    let snap_id = t.test_snap_with_initial_votes(1, 3, 0, &[]).await?;
+    let author = t.authenticate(t.random_sha_256()).await?;
+    t.vote(&snap_id, 1, false, &author).await?;
+    t.submit_review(&snap_id, 1, "this snap is rubbish", &author)
+        .await?;
+    let review_id = t
+        .list_reviews(ListReviewsRequest {
+            snap_id: snap_id.clone(),
+            ..Default::default()
+        })
+        .await?
+        .reviews[0]
+        .id;
+
+    let reporter = t.authenticate(t.random_sha_256()).await?;
+    t.report_content(review_id, ReportReason::Abuse, &reporter)
+        .await?;
+    let reports = open_reports_for(&t, review_id).await?;
+    t.resolve_report(reports[0], ReportState::Actioned, &admin)
+        .await?;
+
+    // Deleting the review keeps the report, but the review can't be written again
+    assert!(t.delete_review(&snap_id, &author).await?);
+    let res = t
+        .submit_review(&snap_id, 1, "this snap is still rubbish", &author)
+        .await;
+    assert_eq!(status_code(res), Code::PermissionDenied);
+    let actioned = t.list_reports(Some(ReportState::Actioned), &admin).await?;
+    let report = actioned.iter().find(|r| r.id == reports[0]).unwrap();
+    assert_eq!(report.review_id, None);
+    assert_eq!(report.review_body, "this snap is rubbish");
+
+    // Recasting the vote doesn't bring it back either
+    t.retract_vote(&snap_id, 1, &author).await?;
+    t.vote(&snap_id, 1, false, &author).await?;
+    assert_eq!(t.get_rating(&snap_id, &reporter).await?.total_votes, 3);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn dismissed_reports_leave_the_review_in_place() -> anyhow::Result<()> {
+    let t = TestHelper::new();
+    let admin = t.admin_token();
+    let (snap_id, review_id) = reviewed_snap(&t, true).await?;
+    let reporter = t.authenticate(t.random_sha_256()).await?;
+    t.report_content(review_id, ReportReason::Misleading, &reporter)
+        .await?;
+    let reports = open_reports_for(&t, review_id).await?;
+
+    assert_eq!(
+        t.resolve_report(reports[0], ReportState::Dismissed, &admin)
+            .await?,
+        1
+    );
+    let res = t
+        .resolve_report(reports[0], ReportState::Actioned, &admin)
+        .await;
+    assert_eq!(status_code(res), Code::NotFound);
+
+    let dismissed = t.list_reports(Some(ReportState::Dismissed), &admin).await?;
+    assert!(dismissed
+        .iter()
+        .any(|r| r.id == reports[0] && r.resolved_at.is_some()));
+    let reviews = t
+        .list_reviews(ListReviewsRequest {
+            snap_id,
+            ..Default::default()
+        })
+        .await?
+        .reviews;
+    assert_eq!(reviews.len(), 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn reports_require_a_reason_and_the_queue_an_admin() -> anyhow::Result<()> {
+    let t = TestHelper::new();
+    let (_, review_id) = reviewed_snap(&t, true).await?;
+    let token = t.authenticate(t.random_sha_256()).await?;
+
+    let res = t
+        .report_content(review_id, ReportReason::Unspecified, &token)
+        .await;
+    assert_eq!(status_code(res), Code::InvalidArgument);
+
+    let res = t.list_reports(None, &token).await;
+    assert_eq!(status_code(res), Code::PermissionDenied);
+
+    let res = t
+        .resolve_report(1, ReportState::Open, &t.admin_token())
+        .await;
+    assert_eq!(status_code(res), Code::InvalidArgument);
+
+    Ok(())
+}
@@ -0,0 +1,75 @@
+pub mod common;
+
+use common::{status_code, TestHelper};
+use ratings::proto::app::{ListReviewsRequest, Review};
+use tonic::Code;
+
+/// Register a snap for the given publisher along with a single review, returning the snap id
+/// and the review
+async fn reviewed_snap(t: &TestHelper, publisher_id: &str) -> anyhow::Result<(String, Review)> {
+    let snap_id = t.test_snap_with_publisher(publisher_id).await?;
+    let token = t.authenticate(t.random_sha_256()).await?;
+    t.submit_review(&snap_id, 1, "missing dark mode", &token)
+        .await?;
+
+    let review = list_reviews(t, &snap_id).await?.remove(0);
+
+    Ok((snap_id, review))
+}
+
+async fn list_reviews(t: &TestHelper, snap_id: &str) -> anyhow::Result<Vec<Review>> {
+    let resp = t
+        .list_reviews(ListReviewsRequest {
+            snap_id: snap_id.to_string(),
+            ..Default::default()
+        })
+        .await?;
+
+    Ok(resp.reviews)
+}
+
+#[tokio::test]
+async fn publishers_can_reply_to_reviews_of_their_snaps() -> anyhow::Result<()> {
+    let t = TestHelper::new();
+    let publisher_id = t.random_id();
+    let token = t.publisher_token(&publisher_id);
+    let (snap_id, review) = reviewed_snap(&t, &publisher_id).await?;
+    assert!(review.reply.is_none());
+
+    t.reply_to_review(review.id, "coming in the next release", &token)
+        .await?;
+    t.reply_to_review(review.id, "available now", &token)
+        .await?;
+
+    let reply = list_reviews(&t, &snap_id).await?[0]
+        .reply
+        .clone()
+        .expect("review to have a reply");
+    assert_eq!(reply.publisher_id, publisher_id);
+    assert_eq!(reply.body, "available now");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn publishers_can_only_reply_to_reviews_of_snaps_they_own() -> anyhow::Result<()> {
+    let t = TestHelper::new();
+    let (snap_id, review) = reviewed_snap(&t, &t.random_id()).await?;
+
+    let other_publisher = t.publisher_token(&t.random_id());
+    let res = t
+        .reply_to_review(review.id, "hello", &other_publisher)
+        .await;
+    assert_eq!(status_code(res), Code::PermissionDenied);
+
+    let user = t.authenticate(t.random_sha_256()).await?;
+    let res = t.reply_to_review(review.id, "hello", &user).await;
+    assert_eq!(status_code(res), Code::PermissionDenied);
+
+    let res = t.reply_to_review(-1, "hello", &other_publisher).await;
+    assert_eq!(status_code(res), Code::NotFound);
+
+    assert!(list_reviews(&t, &snap_id).await?[0].reply.is_none());
+
+    Ok(())
+}
@@ -0,0 +1,109 @@
+pub mod common;
+
+use common::{status_code, TestHelper};
+use ratings::proto::app::ListReviewsRequest;
+use tonic::Code;
+
+fn list_request(snap_id: &str) -> ListReviewsRequest {
+    ListReviewsRequest {
+        snap_id: snap_id.to_string(),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn reviews_can_be_submitted_updated_and_deleted() -> anyhow::Result<()> {
+    let t = TestHelper::new();
+    let snap_id = t.random_id();
+    let token = t.authenticate(t.random_sha_256()).await?;
+
+    t.vote(&snap_id, 2, true, &token).await?;
+    t.submit_review(&snap_id, 1, "  crashes on start  ", &token)
+        .await?;
+    let reviews = t.list_reviews(list_request(&snap_id)).await?.reviews;
+    assert_eq!(reviews.len(), 1);
+    assert_eq!(reviews[0].body, "crashes on start");
+    assert_eq!(reviews[0].snap_revision, 1);
+    assert_eq!(reviews[0].vote_up, None);
+
+    t.submit_review(&snap_id, 2, "fixed now", &token).await?;
+    let reviews = t.list_reviews(list_request(&snap_id)).await?.reviews;
+    assert_eq!(reviews.len(), 1);
+    assert_eq!(reviews[0].body, "fixed now");
+    assert_eq!(reviews[0].snap_revision, 2);
+    assert_eq!(reviews[0].vote_up, Some(true));
+
+    assert!(t.delete_review(&snap_id, &token).await?);
+    assert!(!t.delete_review(&snap_id, &token).await?);
+    assert!(t
+        .list_reviews(list_request(&snap_id))
+        .await?
+        .reviews
+        .is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn invalid_reviews_are_rejected() -> anyhow::Result<()> {
+    let t = TestHelper::new();
+    let snap_id = t.random_id();
+    let token = t.authenticate(t.random_sha_256()).await?;
+
+    let res = t.submit_review(&snap_id, 1, " \n ", &token).await;
+    assert_eq!(status_code(res), Code::InvalidArgument);
+
+    let res = t.submit_review(&snap_id, 1, &"a".repeat(501), &token).await;
+    assert_eq!(status_code(res), Code::InvalidArgument);
+
+    let res = t.submit_review(&snap_id, 0, "great", &token).await;
+    assert_eq!(status_code(res), Code::InvalidArgument);
+
+    let res = t.submit_review(&snap_id, 1, "great", "not-a-token").await;
+    assert_eq!(status_code(res), Code::Unauthenticated);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn reviews_are_paginated_and_filterable_by_revision() -> anyhow::Result<()> {
+    let t = TestHelper::new();
+    let snap_id = t.random_id();
+
+    for i in 0..5 {
+        let token = t.authenticate(t.random_sha_256()).await?;
+        let revision = if i % 2 == 0 { 1 } else { 2 };
+        t.submit_review(&snap_id, revision, &format!("review {i}"), &token)
+            .await?;
+    }
+
+    let mut request = list_request(&snap_id);
+    request.page_size = Some(2);
+    let mut bodies = Vec::new();
+    loop {
+        let resp = t.list_reviews(request.clone()).await?;
+        assert!(resp.reviews.len() <= 2);
+        bodies.extend(resp.reviews.into_iter().map(|r| r.body));
+        if resp.next_page_token.is_empty() {
+            break;
+        }
+        request.page_token = resp.next_page_token;
+    }
+    let expected: Vec<_> = (0..5).rev().map(|i| format!("review {i}")).collect();
+    assert_eq!(bodies, expected);
+
+    let mut request = list_request(&snap_id);
+    request.snap_revision = Some(2);
+    let reviews = t.list_reviews(request).await?.reviews;
+    assert_eq!(reviews.len(), 2);
+    assert!(reviews.iter().all(|r| r.snap_revision == 2));
+
+    let mut request = list_request(&snap_id);
+    request.page_token = "not-a-token".to_string();
+    assert_eq!(
+        status_code(t.list_reviews(request).await),
+        Code::InvalidArgument
+    );
+
+    Ok(())
+}
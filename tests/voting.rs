@@ -1,7 +1,10 @@
 pub mod common;
 
-use common::{Category, TestHelper};
-use ratings::ratings::RatingsBand::{self, *};
+use common::{Category, TestHelper, VoteReason};
+use ratings::{
+    proto::{app::ReasonCount, user::GetSnapVotesRequest},
+    ratings::RatingsBand::{self, *},
+};
 use simple_test_case::test_case;
 
 #[test_case(true; "up vote")]
@@ -120,3 +123,210 @@ async fn voting_on_a_snap_without_categories_works() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn retracting_a_vote_removes_it() -> anyhow::Result<()> {
+    let t = TestHelper::new();
+
+    let user_token = t.authenticate(t.random_sha_256()).await?;
+    let snap_revision = 1;
+    let snap_id = t
+        .test_snap_with_initial_votes(snap_revision, 3, 2, &[Category::Science])
+        .await?;
+
+    t.vote(&snap_id, snap_revision, true, &user_token).await?;
+    let rating = t.get_rating(&snap_id, &user_token).await?;
+    assert_eq!(rating.total_votes, 6, "total votes after voting");
+
+    assert!(t.retract_vote(&snap_id, snap_revision, &user_token).await?);
+    let rating = t.get_rating(&snap_id, &user_token).await?;
+    assert_eq!(rating.total_votes, 5, "total votes after retracting");
+
+    assert!(
+        !t.retract_vote(&snap_id, snap_revision, &user_token).await?,
+        "there should be nothing left to retract"
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn ratings_are_broken_down_by_revision() -> anyhow::Result<()> {
+    let t = TestHelper::new();
+
+    let user_token = t.authenticate(t.random_sha_256()).await?;
+    let snap_id = t
+        .test_snap_with_initial_votes(1, 30, 0, &[Category::Social])
+        .await?;
+    t.generate_votes(&snap_id, 2, false, 30).await?;
+    t.generate_votes(&snap_id, 3, true, 2).await?;
+
+    let revisions = t.get_rating_by_revision(&snap_id, &user_token).await?;
+    let summary: Vec<(i32, u64, u64, RatingsBand)> = revisions
+        .into_iter()
+        .map(|r| {
+            (
+                r.snap_revision,
+                r.total_votes,
+                r.positive_votes,
+                RatingsBand::from_repr(r.ratings_band).unwrap(),
+            )
+        })
+        .collect();
+
+    assert_eq!(
+        summary,
+        vec![
+            (3, 2, 2, InsufficientVotes),
+            (2, 30, 0, VeryPoor),
+            (1, 30, 30, VeryGood),
+        ]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn snaps_unknown_to_snapcraft_are_still_rated() -> anyhow::Result<()> {
+    let t = TestHelper::new();
+
+    // Not registered with the mock server so snapcraft.io returns a 404 for the snap name
+    let user_token = t.authenticate(t.random_sha_256()).await?;
+    let snap_id = t.random_id();
+    t.generate_votes(&snap_id, 1, true, 3).await?;
+    t.vote(&snap_id, 1, false, &user_token).await?;
+
+    let rating = t.get_rating(&snap_id, &user_token).await?;
+    assert_eq!(rating.total_votes, 4);
+
+    let votes = t
+        .get_snap_votes(&user_token, GetSnapVotesRequest { snap_id })
+        .await?;
+    assert_eq!(votes.len(), 1);
+    assert_eq!(votes[0].snap_name, "");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn ratings_can_be_fetched_in_a_batch() -> anyhow::Result<()> {
+    let t = TestHelper::new();
+
+    let user_token = t.authenticate(t.random_sha_256()).await?;
+    let rated = t.test_snap_with_initial_votes(1, 3, 1, &[]).await?;
+    let unrated = t.random_id();
+
+    let ratings = t
+        .get_ratings(&[&unrated, &rated, &unrated], &user_token)
+        .await?;
+    let summary: Vec<_> = ratings
+        .iter()
+        .map(|r| (r.snap_id.as_str(), r.total_votes))
+        .collect();
+    assert_eq!(summary, vec![(unrated.as_str(), 0), (rated.as_str(), 4)]);
+
+    let single = t.get_rating(&rated, &user_token).await?;
+    assert_eq!(ratings[1].ratings_band, single.ratings_band);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn oversized_rating_batches_are_rejected() -> anyhow::Result<()> {
+    let t = TestHelper::new();
+    let user_token = t.authenticate(t.random_sha_256()).await?;
+
+    let ids: Vec<String> = (0..101).map(|_| t.random_id()).collect();
+    let ids: Vec<&str> = ids.iter().map(String::as_str).collect();
+    assert!(t.get_ratings(&ids, &user_token).await.is_err());
+    assert!(t.get_ratings(&ids[..100], &user_token).await.is_ok());
+
+    // Duplicates still count towards the limit
+    let mut with_duplicate = ids[..100].to_vec();
+    with_duplicate.push(ids[0]);
+    assert!(t.get_ratings(&with_duplicate, &user_token).await.is_err());
+    assert!(t.get_ratings(&[], &user_token).await.is_err());
+
+    Ok(())
+}
+
+fn reason_counts(counts: &[ReasonCount]) -> Vec<(VoteReason, u64)> {
+    counts.iter().map(|c| (c.reason(), c.votes)).collect()
+}
+
+#[tokio::test]
+async fn downvote_reasons_are_broken_down_by_revision() -> anyhow::Result<()> {
+    let t = TestHelper::new();
+    let snap_id = t.test_snap_with_initial_votes(1, 2, 0, &[]).await?;
+
+    for (revision, reasons) in [
+        (1, vec![VoteReason::Crashes, VoteReason::Crashes]),
+        (1, vec![VoteReason::Crashes, VoteReason::Performance]),
+        (2, vec![VoteReason::Outdated]),
+        (2, vec![]),
+    ] {
+        let token = t.authenticate(t.random_sha_256()).await?;
+        t.downvote_with_reasons(&snap_id, revision, &reasons, &token)
+            .await?;
+    }
+
+    let token = t.authenticate(t.random_sha_256()).await?;
+    let breakdown = t.get_feedback_breakdown(&snap_id, &token).await?;
+
+    assert_eq!(
+        reason_counts(&breakdown.reasons),
+        vec![
+            (VoteReason::Crashes, 2),
+            (VoteReason::Performance, 1),
+            (VoteReason::Outdated, 1)
+        ]
+    );
+    let revisions: Vec<_> = breakdown
+        .revisions
+        .iter()
+        .map(|r| (r.snap_revision, reason_counts(&r.reasons)))
+        .collect();
+    assert_eq!(
+        revisions,
+        vec![
+            (2, vec![(VoteReason::Outdated, 1)]),
+            (
+                1,
+                vec![(VoteReason::Crashes, 2), (VoteReason::Performance, 1)]
+            ),
+        ]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn reasons_are_only_accepted_for_downvotes() -> anyhow::Result<()> {
+    let t = TestHelper::new();
+    let snap_id = t.random_id();
+    let token = t.authenticate(t.random_sha_256()).await?;
+
+    let res = t
+        .downvote_with_reasons(&snap_id, 1, &[VoteReason::Unspecified], &token)
+        .await;
+    assert!(res.is_err(), "unspecified reasons are rejected");
+
+    t.downvote_with_reasons(&snap_id, 1, &[VoteReason::Other], &token)
+        .await?;
+    let votes = t
+        .get_snap_votes(
+            &token,
+            GetSnapVotesRequest {
+                snap_id: snap_id.clone(),
+            },
+        )
+        .await?;
+    assert_eq!(votes[0].reasons().collect::<Vec<_>>(), [VoteReason::Other]);
+
+    // Changing to an up vote clears the reasons
+    t.vote(&snap_id, 1, true, &token).await?;
+    let breakdown = t.get_feedback_breakdown(&snap_id, &token).await?;
+    assert!(breakdown.reasons.is_empty() && breakdown.revisions.is_empty());
+
+    Ok(())
+}